@@ -1,5 +1,2 @@
-(
-    (def r 10.0)
-    (def pi 3.14159265)
-    (* pi (* r r))
-)
\ No newline at end of file
+(def r 10.0)
+(* pi (* r r))
@@ -1,4 +1,2 @@
-(
-  (def sqr (lambda (r) (* r r)))
-  (sqr 10)
-)
+(def sqr (lambda (r) (* r r)))
+(sqr 10)
@@ -1,7 +1,5 @@
-(
-    (define pi 314)
-    (define r 10)
-    (define sqr (lambda (r) (* r r)))
-    (define area (lambda (r) (* pi (sqr r))))
-    (area r)
-)
\ No newline at end of file
+(def pi 314)
+(def r 10)
+(def sqr (lambda (r) (* r r)))
+(def area (lambda (r) (* pi (sqr r))))
+(area r)
@@ -1,11 +1,9 @@
-(
-    (def fact 
-        (lambda (n) 
-            (if (< n 1)
-                1 
-                (* n (fact (- n 1)))
-            )
+(def fact 
+    (lambda (n &optional (acc 1)) 
+        (if (< n 1)
+            acc 
+            (fact (- n 1) (* acc n))
         )
     )
-    (fact 5)
-)
\ No newline at end of file
+)
+(fact 5)
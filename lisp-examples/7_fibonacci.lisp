@@ -1,11 +1,9 @@
-(
-    (def fib
-        (lambda (n) 
-            (if (< n 2)
-                n
-                (+ (fib (- n 1)) (fib (- n 2)))
-            )
+(def fib
+    (lambda (n) 
+        (if (< n 2)
+            n
+            (+ (fib (- n 1)) (fib (- n 2)))
         )
     )
-    (fib 10)
-)
\ No newline at end of file
+)
+(fib 10)
@@ -0,0 +1,88 @@
+use crate::error::LispError;
+use crate::object::{BuiltinFn, Object};
+
+use super::*;
+
+pub const BUILTINS: &[(&str, BuiltinFn)] = &[
+    ("char?", is_char),
+    ("char->integer", char_to_integer),
+    ("integer->char", integer_to_char),
+    ("char-upcase", char_upcase),
+    ("char-downcase", char_downcase),
+    ("char-alphabetic?", is_char_alphabetic),
+    ("char-numeric?", is_char_numeric),
+    ("char-whitespace?", is_char_whitespace),
+    ("char-upper-case?", is_char_upper_case),
+    ("char-lower-case?", is_char_lower_case),
+];
+
+fn is_char(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("char?", args, 1)?;
+    Ok(Object::Bool(matches!(args[0], Object::Char(_))))
+}
+
+fn char_to_integer(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("char->integer", args, 1)?;
+    Ok(Object::Integer(expect_char(&args[0])? as i64))
+}
+
+fn integer_to_char(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("integer->char", args, 1)?;
+    let n = expect_integer(&args[0])?;
+    u32::try_from(n)
+        .ok()
+        .and_then(char::from_u32)
+        .map(Object::Char)
+        .ok_or_else(|| LispError::invalid_form(format!("{} is not a character code", n)))
+}
+
+/// Characters whose case mapping is more than one character are left as
+/// they are.
+fn char_upcase(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("char-upcase", args, 1)?;
+    let c = expect_char(&args[0])?;
+    let mut upper = c.to_uppercase();
+    Ok(Object::Char(match (upper.next(), upper.next()) {
+        (Some(u), None) => u,
+        _ => c,
+    }))
+}
+
+fn char_downcase(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("char-downcase", args, 1)?;
+    let c = expect_char(&args[0])?;
+    let mut lower = c.to_lowercase();
+    Ok(Object::Char(match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }))
+}
+
+fn char_predicate(
+    name: &str,
+    args: &[Object],
+    pred: fn(char) -> bool,
+) -> Result<Object, LispError> {
+    expect_arity(name, args, 1)?;
+    Ok(Object::Bool(pred(expect_char(&args[0])?)))
+}
+
+fn is_char_alphabetic(args: &[Object]) -> Result<Object, LispError> {
+    char_predicate("char-alphabetic?", args, char::is_alphabetic)
+}
+
+fn is_char_numeric(args: &[Object]) -> Result<Object, LispError> {
+    char_predicate("char-numeric?", args, char::is_numeric)
+}
+
+fn is_char_whitespace(args: &[Object]) -> Result<Object, LispError> {
+    char_predicate("char-whitespace?", args, char::is_whitespace)
+}
+
+fn is_char_upper_case(args: &[Object]) -> Result<Object, LispError> {
+    char_predicate("char-upper-case?", args, char::is_uppercase)
+}
+
+fn is_char_lower_case(args: &[Object]) -> Result<Object, LispError> {
+    char_predicate("char-lower-case?", args, char::is_lowercase)
+}
@@ -0,0 +1,30 @@
+use std::rc::Rc;
+
+use crate::error::LispError;
+use crate::object::{BuiltinFn, Object};
+
+use super::*;
+
+pub const BUILTINS: &[(&str, BuiltinFn)] = &[("not", not), ("eq?", eq), ("void?", is_void)];
+
+fn not(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("not", args, 1)?;
+    Ok(Object::Bool(!args[0].is_truthy()))
+}
+
+/// Vectors and hash maps are only `eq?` to themselves, since they can be
+/// mutated; every other value, symbols included, compares by value.
+fn eq(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("eq?", args, 2)?;
+    let same = match (&args[0], &args[1]) {
+        (Object::Vector(a), Object::Vector(b)) => Rc::ptr_eq(a, b),
+        (Object::HashMap(a), Object::HashMap(b)) => Rc::ptr_eq(&a.0, &b.0),
+        (a, b) => a == b,
+    };
+    Ok(Object::Bool(same))
+}
+
+fn is_void(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("void?", args, 1)?;
+    Ok(Object::Bool(matches!(args[0], Object::Void)))
+}
@@ -0,0 +1,125 @@
+use crate::error::{ErrorKind, LispError};
+use crate::eval;
+use crate::object::{BuiltinFn, HashKey, HashTable, Object, Span};
+
+use super::*;
+
+pub const BUILTINS: &[(&str, BuiltinFn)] = &[
+    ("hash-map", hash_map),
+    ("hash-map?", is_hash_map),
+    ("hash-ref", hash_ref),
+    ("hash-set!", hash_set),
+    ("hash-remove!", hash_remove),
+    ("hash-contains?", hash_contains),
+    ("hash-keys", hash_keys),
+    ("hash-values", hash_values),
+    ("hash-count", hash_count),
+    ("hash->list", hash_to_list),
+    ("hash-for-each", hash_for_each),
+];
+
+fn expect_hash_map(obj: &Object) -> Result<&HashTable, LispError> {
+    match obj {
+        Object::HashMap(table) => Ok(table),
+        o => Err(LispError::type_mismatch("HashMap", o.type_name())),
+    }
+}
+
+/// `(hash-map k1 v1 k2 v2 ...)` builds the same table as the literal
+/// `{k1 v1 k2 v2 ...}`.
+fn hash_map(args: &[Object]) -> Result<Object, LispError> {
+    if !args.len().is_multiple_of(2) {
+        return Err(LispError::arity("hash-map", "an even number", args.len()));
+    }
+    Ok(Object::HashMap(HashTable::from_pairs(args)?))
+}
+
+fn is_hash_map(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("hash-map?", args, 1)?;
+    Ok(Object::Bool(matches!(args[0], Object::HashMap(_))))
+}
+
+/// `(hash-ref table key [default])` fails on a missing key unless a default
+/// is given.
+fn hash_ref(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity_range("hash-ref", args, 2, 3)?;
+    let table = expect_hash_map(&args[0])?.0.borrow();
+    match (table.get(&HashKey::new(&args[1])?), args.get(2)) {
+        (Some(v), _) => Ok(v.clone()),
+        (None, Some(default)) => Ok(default.clone()),
+        (None, None) => Err(LispError::new(ErrorKind::KeyNotFound(args[1].to_string()))),
+    }
+}
+
+fn hash_set(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("hash-set!", args, 3)?;
+    let key = HashKey::new(&args[1])?;
+    expect_hash_map(&args[0])?
+        .0
+        .borrow_mut()
+        .insert(key, args[2].clone());
+    Ok(Object::Void)
+}
+
+fn hash_remove(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("hash-remove!", args, 2)?;
+    let key = HashKey::new(&args[1])?;
+    expect_hash_map(&args[0])?.0.borrow_mut().remove(&key);
+    Ok(Object::Void)
+}
+
+fn hash_contains(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("hash-contains?", args, 2)?;
+    let key = HashKey::new(&args[1])?;
+    let table = expect_hash_map(&args[0])?.0.borrow();
+    Ok(Object::Bool(table.contains_key(&key)))
+}
+
+fn hash_keys(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("hash-keys", args, 1)?;
+    let table = expect_hash_map(&args[0])?.0.borrow();
+    let keys = table.keys().map(|k| k.0.clone()).collect();
+    Ok(Object::List(keys, Span::default()))
+}
+
+fn hash_values(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("hash-values", args, 1)?;
+    let table = expect_hash_map(&args[0])?.0.borrow();
+    let values = table.values().cloned().collect();
+    Ok(Object::List(values, Span::default()))
+}
+
+fn hash_count(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("hash-count", args, 1)?;
+    let table = expect_hash_map(&args[0])?.0.borrow();
+    Ok(Object::Integer(table.len() as i64))
+}
+
+/// Copies the entries out as a list of `(key value)` lists.
+fn entries(table: &HashTable) -> Vec<Object> {
+    table
+        .0
+        .borrow()
+        .iter()
+        .map(|(k, v)| Object::List(vec![k.0.clone(), v.clone()].into(), Span::default()))
+        .collect()
+}
+
+fn hash_to_list(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("hash->list", args, 1)?;
+    let table = expect_hash_map(&args[0])?;
+    Ok(Object::List(entries(table).into(), Span::default()))
+}
+
+/// `(hash-for-each table proc)` calls `(proc key value)` for every entry. The
+/// entries are copied first so `proc` may modify the table.
+fn hash_for_each(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("hash-for-each", args, 2)?;
+    let table = expect_hash_map(&args[0])?;
+    for entry in entries(table) {
+        if let Object::List(pair, _) = entry {
+            eval::apply(&args[1], &pair)?;
+        }
+    }
+    Ok(Object::Void)
+}
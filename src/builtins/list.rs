@@ -0,0 +1,93 @@
+use std::rc::Rc;
+
+use crate::error::LispError;
+use crate::object::{BuiltinFn, Object, Pair, Span};
+
+use super::*;
+
+pub const BUILTINS: &[(&str, BuiltinFn)] = &[
+    ("list", list),
+    ("cons", cons),
+    ("car", car),
+    ("cdr", cdr),
+    ("pair?", is_pair),
+    ("null?", is_null),
+    ("length", length),
+    ("append", append),
+    ("reverse", reverse),
+];
+
+fn list(args: &[Object]) -> Result<Object, LispError> {
+    Ok(Object::List(
+        args.iter().cloned().collect(),
+        Span::default(),
+    ))
+}
+
+/// Makes a pair that shares its tail instead of copying it. The tail does
+/// not have to be a list: `(cons 1 2)` is the improper list `(1 . 2)`.
+fn cons(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("cons", args, 2)?;
+    Ok(Object::Pair(Rc::new(Pair {
+        car: args[0].clone(),
+        cdr: args[1].clone(),
+    })))
+}
+
+/// Splits the argument of `car` or `cdr`.
+fn uncons(name: &str, obj: &Object) -> Result<(Object, Object), LispError> {
+    match (obj.uncons(), obj) {
+        (Some(split), _) => Ok(split),
+        (None, Object::List(_, _)) => Err(LispError::invalid_form(format!(
+            "{} of an empty list",
+            name
+        ))),
+        (None, o) => Err(LispError::type_mismatch("List", o.type_name())),
+    }
+}
+
+fn car(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("car", args, 1)?;
+    Ok(uncons("car", &args[0])?.0)
+}
+
+fn cdr(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("cdr", args, 1)?;
+    Ok(uncons("cdr", &args[0])?.1)
+}
+
+fn is_pair(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("pair?", args, 1)?;
+    Ok(Object::Bool(match &args[0] {
+        Object::Pair(_) => true,
+        Object::List(l, _) => !l.is_empty(),
+        _ => false,
+    }))
+}
+
+fn is_null(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("null?", args, 1)?;
+    Ok(Object::Bool(
+        matches!(&args[0], Object::List(l, _) if l.is_empty()),
+    ))
+}
+
+fn length(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("length", args, 1)?;
+    Ok(Object::Integer(expect_list(&args[0])?.len() as i64))
+}
+
+fn append(args: &[Object]) -> Result<Object, LispError> {
+    let mut res = Vec::new();
+    for o in args {
+        res.extend_from_slice(&expect_list(o)?);
+    }
+    Ok(Object::List(res.into(), Span::default()))
+}
+
+fn reverse(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("reverse", args, 1)?;
+    let mut l = expect_list(&args[0])?.to_vec();
+    l.reverse();
+    Ok(Object::List(l.into(), Span::default()))
+}
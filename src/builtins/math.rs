@@ -0,0 +1,197 @@
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Signed, Zero};
+
+use crate::error::LispError;
+use crate::number;
+use crate::object::{BuiltinFn, Object};
+
+use super::*;
+
+pub const CONSTANTS: &[(&str, f64)] = &[("pi", std::f64::consts::PI), ("e", std::f64::consts::E)];
+
+pub const BUILTINS: &[(&str, BuiltinFn)] = &[
+    ("sqrt", sqrt),
+    ("expt", expt),
+    ("exp", exp),
+    ("log", log),
+    ("sin", sin),
+    ("cos", cos),
+    ("tan", tan),
+    ("asin", asin),
+    ("acos", acos),
+    ("atan", atan),
+    ("floor", floor),
+    ("ceiling", ceiling),
+    ("round", round),
+    ("truncate", truncate),
+    ("exact->inexact", exact_to_inexact),
+    ("inexact->exact", inexact_to_exact),
+];
+
+/// Applies `f` to the single argument converted to a float.
+fn float_fn(name: &str, args: &[Object], f: fn(f64) -> f64) -> Result<Object, LispError> {
+    expect_arity(name, args, 1)?;
+    Ok(Object::Float(f(number::to_float(&args[0])?)))
+}
+
+/// Exact square root of a non-negative integer, if it has one.
+fn exact_sqrt(n: &BigInt) -> Option<BigInt> {
+    if n.is_negative() {
+        return None;
+    }
+    let root = n.sqrt();
+    (&root * &root == *n).then_some(root)
+}
+
+/// Exact for perfect squares such as `4` or `1/9`, a float otherwise.
+fn sqrt(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("sqrt", args, 1)?;
+    let exact = match &args[0] {
+        Object::Integer(n) => exact_sqrt(&BigInt::from(*n)).map(number::from_bigint),
+        Object::BigInteger(n) => exact_sqrt(n).map(number::from_bigint),
+        Object::Rational(r) => exact_sqrt(r.numer())
+            .zip(exact_sqrt(r.denom()))
+            .map(|(numer, denom)| number::from_ratio(BigRational::new(numer, denom))),
+        _ => None,
+    };
+    match exact {
+        Some(o) => Ok(o),
+        None => Ok(Object::Float(number::to_float(&args[0])?.sqrt())),
+    }
+}
+
+/// `(expt base power)` stays exact for an exact base and integer power.
+fn expt(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("expt", args, 2)?;
+    let (base, power) = (&args[0], &args[1]);
+    let exact_base = match base {
+        Object::Integer(n) => Some(BigRational::from_integer(BigInt::from(*n))),
+        Object::BigInteger(n) => Some(BigRational::from_integer(n.clone())),
+        Object::Rational(r) => Some(r.clone()),
+        _ => None,
+    };
+    match (exact_base, power) {
+        (Some(base), Object::Integer(p)) => {
+            let exp = i32::try_from(*p).map_err(|_| LispError::overflow("expt"))?;
+            if exp < 0 && base.is_zero() {
+                return Err(LispError::new(ErrorKind::DivisionByZero));
+            }
+            Ok(number::from_ratio(base.pow(exp)))
+        }
+        (_, Object::BigInteger(_)) if number::is_number(base) => Err(LispError::overflow("expt")),
+        _ => Ok(Object::Float(
+            number::to_float(base)?.powf(number::to_float(power)?),
+        )),
+    }
+}
+
+fn exp(args: &[Object]) -> Result<Object, LispError> {
+    float_fn("exp", args, f64::exp)
+}
+
+/// `(log x)` is the natural logarithm, `(log x base)` uses the given base.
+fn log(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity_range("log", args, 1, 2)?;
+    let x = number::to_float(&args[0])?;
+    match args.get(1) {
+        Some(base) => Ok(Object::Float(x.log(number::to_float(base)?))),
+        None => Ok(Object::Float(x.ln())),
+    }
+}
+
+fn sin(args: &[Object]) -> Result<Object, LispError> {
+    float_fn("sin", args, f64::sin)
+}
+
+fn cos(args: &[Object]) -> Result<Object, LispError> {
+    float_fn("cos", args, f64::cos)
+}
+
+fn tan(args: &[Object]) -> Result<Object, LispError> {
+    float_fn("tan", args, f64::tan)
+}
+
+fn asin(args: &[Object]) -> Result<Object, LispError> {
+    float_fn("asin", args, f64::asin)
+}
+
+fn acos(args: &[Object]) -> Result<Object, LispError> {
+    float_fn("acos", args, f64::acos)
+}
+
+/// `(atan y x)` gives the angle of the point `(x, y)`, like `atan2`.
+fn atan(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity_range("atan", args, 1, 2)?;
+    let y = number::to_float(&args[0])?;
+    match args.get(1) {
+        Some(x) => Ok(Object::Float(y.atan2(number::to_float(x)?))),
+        None => Ok(Object::Float(y.atan())),
+    }
+}
+
+/// Rounds a number to an integral value: integers are returned as they are,
+/// rationals become exact integers and floats stay floats.
+fn round_with(
+    name: &str,
+    args: &[Object],
+    exact: fn(&BigRational) -> BigRational,
+    inexact: fn(f64) -> f64,
+) -> Result<Object, LispError> {
+    expect_arity(name, args, 1)?;
+    match &args[0] {
+        o @ (Object::Integer(_) | Object::BigInteger(_)) => Ok(o.clone()),
+        Object::Rational(r) => Ok(number::from_ratio(exact(r))),
+        Object::Float(f) => Ok(Object::Float(inexact(*f))),
+        o => Err(LispError::type_mismatch("Number", o.type_name())),
+    }
+}
+
+fn floor(args: &[Object]) -> Result<Object, LispError> {
+    round_with("floor", args, BigRational::floor, f64::floor)
+}
+
+fn ceiling(args: &[Object]) -> Result<Object, LispError> {
+    round_with("ceiling", args, BigRational::ceil, f64::ceil)
+}
+
+/// Halves round to the nearest even integer, `(round 2.5)` is `2.0`.
+fn round(args: &[Object]) -> Result<Object, LispError> {
+    round_with("round", args, round_ratio_even, f64::round_ties_even)
+}
+
+fn round_ratio_even(r: &BigRational) -> BigRational {
+    let floor = r.floor();
+    let diff = r - &floor;
+    let half = BigRational::new(1.into(), 2.into());
+    if diff > half || (diff == half && floor.to_integer().bit(0)) {
+        floor + BigRational::from_integer(1.into())
+    } else {
+        floor
+    }
+}
+
+fn truncate(args: &[Object]) -> Result<Object, LispError> {
+    round_with("truncate", args, BigRational::trunc, f64::trunc)
+}
+
+fn exact_to_inexact(args: &[Object]) -> Result<Object, LispError> {
+    float_fn("exact->inexact", args, |f| f)
+}
+
+/// Floats convert to the exact rational they represent, `(inexact->exact 0.5)`
+/// is `1/2`.
+fn inexact_to_exact(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("inexact->exact", args, 1)?;
+    match &args[0] {
+        Object::Float(f) => match BigRational::from_float(*f) {
+            Some(r) => Ok(number::from_ratio(r)),
+            None => Err(LispError::invalid_form(format!(
+                "{} has no exact representation",
+                f
+            ))),
+        },
+        o if number::is_number(o) => Ok(o.clone()),
+        o => Err(LispError::type_mismatch("Number", o.type_name())),
+    }
+}
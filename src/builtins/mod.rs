@@ -0,0 +1,107 @@
+use std::borrow::Cow;
+
+use crate::error::{ErrorKind, LispError};
+use crate::object::{Builtin, Object};
+use crate::scope::Scope;
+
+mod char;
+mod core;
+mod hash;
+mod list;
+mod math;
+mod number;
+mod string;
+mod symbol;
+mod vector;
+
+/// Defines every builtin procedure and constant in the given (global) scope.
+pub fn register(scope: &mut Scope) {
+    let tables = [
+        char::BUILTINS,
+        core::BUILTINS,
+        hash::BUILTINS,
+        list::BUILTINS,
+        math::BUILTINS,
+        number::BUILTINS,
+        string::BUILTINS,
+        symbol::BUILTINS,
+        vector::BUILTINS,
+    ];
+    for (name, func) in tables.into_iter().flatten() {
+        scope.set(name, Object::Builtin(Builtin { name, func: *func }));
+    }
+    for (name, value) in math::CONSTANTS {
+        scope.set(name, Object::Float(*value));
+    }
+}
+
+pub fn expect_arity(name: &str, args: &[Object], expected: usize) -> Result<(), LispError> {
+    if args.len() != expected {
+        return Err(LispError::arity(name, expected, args.len()));
+    }
+    Ok(())
+}
+
+pub fn expect_arity_range(
+    name: &str,
+    args: &[Object],
+    min: usize,
+    max: usize,
+) -> Result<(), LispError> {
+    if args.len() < min || args.len() > max {
+        return Err(LispError::arity(
+            name,
+            format!("{} to {}", min, max),
+            args.len(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn expect_string(obj: &Object) -> Result<&str, LispError> {
+    match obj {
+        Object::String(s) => Ok(s),
+        o => Err(LispError::type_mismatch("String", o.type_name())),
+    }
+}
+
+pub fn expect_char(obj: &Object) -> Result<char, LispError> {
+    match obj {
+        Object::Char(c) => Ok(*c),
+        o => Err(LispError::type_mismatch("Char", o.type_name())),
+    }
+}
+
+/// The elements of a proper list, which are copied out if it was built by
+/// `cons`.
+pub fn expect_list(obj: &Object) -> Result<Cow<'_, [Object]>, LispError> {
+    obj.list_items()
+        .ok_or_else(|| LispError::type_mismatch("List", obj.type_name()))
+}
+
+pub fn expect_integer(obj: &Object) -> Result<i64, LispError> {
+    match obj {
+        Object::Integer(n) => Ok(*n),
+        Object::BigInteger(_) => Err(LispError::overflow("conversion to a 64-bit integer")),
+        o => Err(LispError::type_mismatch("Integer", o.type_name())),
+    }
+}
+
+/// Converts a Lisp index into a `usize` that is at most `len`, as needed for
+/// the bounds of a slice.
+pub fn expect_index(obj: &Object, len: usize) -> Result<usize, LispError> {
+    let index = expect_integer(obj)?;
+    if index < 0 || index as usize > len {
+        return Err(LispError::new(ErrorKind::IndexOutOfBounds { index, len }));
+    }
+    Ok(index as usize)
+}
+
+/// Converts a Lisp index into a `usize` that refers to one of `len` elements.
+pub fn expect_element_index(obj: &Object, len: usize) -> Result<usize, LispError> {
+    let index = expect_integer(obj)?;
+    if index < 0 || index as usize >= len {
+        return Err(LispError::new(ErrorKind::IndexOutOfBounds { index, len }));
+    }
+    Ok(index as usize)
+}
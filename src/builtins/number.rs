@@ -0,0 +1,66 @@
+use std::cmp::Ordering;
+
+use crate::error::LispError;
+use crate::number;
+use crate::object::{BuiltinFn, Object};
+
+use super::*;
+
+pub const BUILTINS: &[(&str, BuiltinFn)] = &[
+    ("mod", modulo),
+    ("rem", rem),
+    ("quotient", quotient),
+    ("abs", abs),
+    ("min", min),
+    ("max", max),
+];
+
+fn modulo(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("mod", args, 2)?;
+    number::modulo(&args[0], &args[1])
+}
+
+fn rem(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("rem", args, 2)?;
+    number::rem(&args[0], &args[1])
+}
+
+fn quotient(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("quotient", args, 2)?;
+    number::quotient(&args[0], &args[1])
+}
+
+fn abs(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("abs", args, 1)?;
+    number::abs(&args[0])
+}
+
+fn min(args: &[Object]) -> Result<Object, LispError> {
+    extremum("min", args, Ordering::Less)
+}
+
+fn max(args: &[Object]) -> Result<Object, LispError> {
+    extremum("max", args, Ordering::Greater)
+}
+
+/// Picks the argument that orders as `wanted` against all others. The result
+/// is a float if any argument is.
+fn extremum(name: &str, args: &[Object], wanted: Ordering) -> Result<Object, LispError> {
+    let (first, rest) = match args.split_first() {
+        Some(split) => split,
+        None => return Err(LispError::arity(name, "at least 1", 0)),
+    };
+    if !number::is_number(first) {
+        return Err(LispError::type_mismatch("Number", first.type_name()));
+    }
+    let mut best = first;
+    for o in rest {
+        if number::compare(o, best)? == Some(wanted) {
+            best = o;
+        }
+    }
+    if args.iter().any(number::is_float) {
+        return Ok(Object::Float(number::to_float(best)?));
+    }
+    Ok(best.clone())
+}
@@ -0,0 +1,107 @@
+use crate::error::LispError;
+use crate::number;
+use crate::object::{BuiltinFn, Object, Span};
+
+use super::*;
+
+pub const BUILTINS: &[(&str, BuiltinFn)] = &[
+    ("string-append", string_append),
+    ("string-length", string_length),
+    ("substring", substring),
+    ("string-contains", string_contains),
+    ("string-upcase", string_upcase),
+    ("string-downcase", string_downcase),
+    ("string->list", string_to_list),
+    ("list->string", list_to_string),
+    ("number->string", number_to_string),
+    ("string->number", string_to_number),
+];
+
+fn string_append(args: &[Object]) -> Result<Object, LispError> {
+    let mut res = String::new();
+    for o in args {
+        res.push_str(expect_string(o)?);
+    }
+    Ok(Object::String(res))
+}
+
+fn string_length(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("string-length", args, 1)?;
+    let s = expect_string(&args[0])?;
+    Ok(Object::Integer(s.chars().count() as i64))
+}
+
+/// `(substring s start [end])`, indices count characters.
+fn substring(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity_range("substring", args, 2, 3)?;
+    let s = expect_string(&args[0])?;
+    let len = s.chars().count();
+    let start = expect_index(&args[1], len)?;
+    let end = match args.get(2) {
+        Some(o) => expect_index(o, len)?,
+        None => len,
+    };
+    if start > end {
+        return Err(LispError::invalid_form(format!(
+            "substring start {} is after end {}",
+            start, end
+        )));
+    }
+    Ok(Object::String(
+        s.chars().skip(start).take(end - start).collect(),
+    ))
+}
+
+/// `(string-contains s pattern)` returns the character index of the first
+/// occurrence of `pattern` in `s`, or `#f`.
+fn string_contains(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("string-contains", args, 2)?;
+    let s = expect_string(&args[0])?;
+    let pattern = expect_string(&args[1])?;
+    match s.find(pattern) {
+        Some(i) => Ok(Object::Integer(s[..i].chars().count() as i64)),
+        None => Ok(Object::Bool(false)),
+    }
+}
+
+fn string_upcase(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("string-upcase", args, 1)?;
+    Ok(Object::String(expect_string(&args[0])?.to_uppercase()))
+}
+
+fn string_downcase(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("string-downcase", args, 1)?;
+    Ok(Object::String(expect_string(&args[0])?.to_lowercase()))
+}
+
+fn string_to_list(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("string->list", args, 1)?;
+    let s = expect_string(&args[0])?;
+    Ok(Object::List(
+        s.chars().map(Object::Char).collect(),
+        Span::default(),
+    ))
+}
+
+fn list_to_string(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("list->string", args, 1)?;
+    let chars = expect_list(&args[0])?;
+    Ok(Object::String(
+        chars.iter().map(expect_char).collect::<Result<_, _>>()?,
+    ))
+}
+
+fn number_to_string(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("number->string", args, 1)?;
+    match &args[0] {
+        o if number::is_number(o) => Ok(Object::String(o.to_string())),
+        o => Err(LispError::type_mismatch("Number", o.type_name())),
+    }
+}
+
+/// Returns `#f` when the string is not a valid number.
+fn string_to_number(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("string->number", args, 1)?;
+    let s = expect_string(&args[0])?.trim();
+    Ok(number::parse(s).unwrap_or(Object::Bool(false)))
+}
@@ -0,0 +1,51 @@
+use crate::error::LispError;
+use crate::object::{BuiltinFn, Object};
+
+use super::*;
+
+pub const BUILTINS: &[(&str, BuiltinFn)] = &[
+    ("symbol?", is_symbol),
+    ("symbol->string", symbol_to_string),
+    ("string->symbol", string_to_symbol),
+    ("keyword?", is_keyword),
+    ("keyword->string", keyword_to_string),
+    ("string->keyword", string_to_keyword),
+];
+
+/// Quoted special form names, `'if` and operators like `'+` are symbols too.
+fn is_symbol(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("symbol?", args, 1)?;
+    Ok(Object::Bool(args[0].symbol_name().is_some()))
+}
+
+fn symbol_to_string(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("symbol->string", args, 1)?;
+    match args[0].symbol_name() {
+        Some(name) => Ok(Object::String(name.into_owned())),
+        None => Err(LispError::type_mismatch("Name", args[0].type_name())),
+    }
+}
+
+fn string_to_symbol(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("string->symbol", args, 1)?;
+    Ok(Object::Name(expect_string(&args[0])?.to_string()))
+}
+
+fn is_keyword(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("keyword?", args, 1)?;
+    Ok(Object::Bool(matches!(args[0], Object::Keyword(_))))
+}
+
+/// The name without its leading colon, `(keyword->string :a)` is `"a"`.
+fn keyword_to_string(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("keyword->string", args, 1)?;
+    match &args[0] {
+        Object::Keyword(s) => Ok(Object::String(s.clone())),
+        o => Err(LispError::type_mismatch("Keyword", o.type_name())),
+    }
+}
+
+fn string_to_keyword(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("string->keyword", args, 1)?;
+    Ok(Object::Keyword(expect_string(&args[0])?.to_string()))
+}
@@ -0,0 +1,111 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::error::LispError;
+use crate::object::{BuiltinFn, Object, Span};
+
+use super::*;
+
+pub const BUILTINS: &[(&str, BuiltinFn)] = &[
+    ("vector", vector),
+    ("vector?", is_vector),
+    ("make-vector", make_vector),
+    ("vector-ref", vector_ref),
+    ("vector-set!", vector_set),
+    ("vector-length", vector_length),
+    ("vector-slice", vector_slice),
+    ("vector->list", vector_to_list),
+    ("list->vector", list_to_vector),
+];
+
+fn expect_vector(obj: &Object) -> Result<&Rc<RefCell<Vec<Object>>>, LispError> {
+    match obj {
+        Object::Vector(v) => Ok(v),
+        o => Err(LispError::type_mismatch("Vector", o.type_name())),
+    }
+}
+
+fn new_vector(elements: Vec<Object>) -> Object {
+    Object::Vector(Rc::new(RefCell::new(elements)))
+}
+
+/// `(vector a b ...)` builds the same vector as the literal `#(a b ...)`.
+fn vector(args: &[Object]) -> Result<Object, LispError> {
+    Ok(new_vector(args.to_vec()))
+}
+
+fn is_vector(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("vector?", args, 1)?;
+    Ok(Object::Bool(matches!(args[0], Object::Vector(_))))
+}
+
+/// `(make-vector n [fill])`, elements default to `Void`.
+fn make_vector(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity_range("make-vector", args, 1, 2)?;
+    let len = expect_integer(&args[0])?;
+    if len < 0 {
+        return Err(LispError::invalid_form(format!(
+            "make-vector length {} is negative",
+            len
+        )));
+    }
+    let fill = args.get(1).cloned().unwrap_or(Object::Void);
+    let mut elements = Vec::new();
+    elements
+        .try_reserve_exact(len as usize)
+        .map_err(|_| LispError::invalid_form(format!("make-vector length {} is too large", len)))?;
+    elements.resize(len as usize, fill);
+    Ok(new_vector(elements))
+}
+
+fn vector_ref(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("vector-ref", args, 2)?;
+    let v = expect_vector(&args[0])?.borrow();
+    let index = expect_element_index(&args[1], v.len())?;
+    Ok(v[index].clone())
+}
+
+fn vector_set(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("vector-set!", args, 3)?;
+    let mut v = expect_vector(&args[0])?.borrow_mut();
+    let index = expect_element_index(&args[1], v.len())?;
+    v[index] = args[2].clone();
+    Ok(Object::Void)
+}
+
+fn vector_length(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("vector-length", args, 1)?;
+    Ok(Object::Integer(
+        expect_vector(&args[0])?.borrow().len() as i64
+    ))
+}
+
+/// `(vector-slice v start [end])` copies the elements from `start` up to,
+/// but not including, `end` into a new vector.
+fn vector_slice(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity_range("vector-slice", args, 2, 3)?;
+    let v = expect_vector(&args[0])?.borrow();
+    let start = expect_index(&args[1], v.len())?;
+    let end = match args.get(2) {
+        Some(o) => expect_index(o, v.len())?,
+        None => v.len(),
+    };
+    if start > end {
+        return Err(LispError::invalid_form(format!(
+            "vector-slice start {} is after end {}",
+            start, end
+        )));
+    }
+    Ok(new_vector(v[start..end].to_vec()))
+}
+
+fn vector_to_list(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("vector->list", args, 1)?;
+    let v = expect_vector(&args[0])?.borrow();
+    Ok(Object::List(v.clone().into(), Span::default()))
+}
+
+fn list_to_vector(args: &[Object]) -> Result<Object, LispError> {
+    expect_arity("list->vector", args, 1)?;
+    Ok(new_vector(expect_list(&args[0])?.to_vec()))
+}
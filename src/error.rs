@@ -0,0 +1,122 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::object::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    Parse(String),
+    UnboundSymbol(String),
+    Arity {
+        name: String,
+        expected: String,
+        found: usize,
+    },
+    TypeMismatch {
+        expected: String,
+        found: String,
+    },
+    DivisionByZero,
+    Overflow(String),
+    IndexOutOfBounds {
+        index: i64,
+        len: usize,
+    },
+    KeyNotFound(String),
+    InvalidForm(String),
+}
+
+/// Error produced while reading or evaluating a program. The span points at
+/// the innermost form that failed, if it is known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LispError {
+    pub kind: ErrorKind,
+    pub span: Option<Span>,
+}
+
+impl LispError {
+    pub fn new(kind: ErrorKind) -> Self {
+        LispError { kind, span: None }
+    }
+
+    pub fn parse(msg: impl Into<String>, span: Span) -> Self {
+        LispError {
+            kind: ErrorKind::Parse(msg.into()),
+            span: Some(span),
+        }
+    }
+
+    pub fn unbound(name: &str) -> Self {
+        Self::new(ErrorKind::UnboundSymbol(name.to_string()))
+    }
+
+    pub fn arity(name: &str, expected: impl ToString, found: usize) -> Self {
+        Self::new(ErrorKind::Arity {
+            name: name.to_string(),
+            expected: expected.to_string(),
+            found,
+        })
+    }
+
+    pub fn type_mismatch(expected: &str, found: &str) -> Self {
+        Self::new(ErrorKind::TypeMismatch {
+            expected: expected.to_string(),
+            found: found.to_string(),
+        })
+    }
+
+    pub fn overflow(op: &str) -> Self {
+        Self::new(ErrorKind::Overflow(op.to_string()))
+    }
+
+    pub fn invalid_form(msg: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidForm(msg.into()))
+    }
+
+    /// Attaches a span unless a more precise one was already recorded or the
+    /// span is unknown.
+    pub fn with_span(mut self, span: Span) -> Self {
+        if self.span.is_none() && span.is_known() {
+            self.span = Some(span);
+        }
+        self
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::Parse(s) => write!(f, "Parse error: {}", s),
+            ErrorKind::UnboundSymbol(s) => write!(f, "Unbound symbol: {}", s),
+            ErrorKind::Arity {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Wrong number of arguments for {}: expected {}, found {}",
+                name, expected, found
+            ),
+            ErrorKind::TypeMismatch { expected, found } => {
+                write!(f, "Type mismatch: expected {}, found {}", expected, found)
+            }
+            ErrorKind::DivisionByZero => write!(f, "Division by zero"),
+            ErrorKind::Overflow(op) => write!(f, "Integer overflow in {}", op),
+            ErrorKind::IndexOutOfBounds { index, len } => {
+                write!(f, "Index {} out of bounds for length {}", index, len)
+            }
+            ErrorKind::KeyNotFound(k) => write!(f, "Key not found: {}", k),
+            ErrorKind::InvalidForm(s) => write!(f, "Invalid form: {}", s),
+        }
+    }
+}
+
+impl Display for LispError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl Error for LispError {}
@@ -1,342 +1,1404 @@
-use crate::object::Op;
-use crate::{lexer, parser};
+use crate::error::LispError;
+use crate::object::Span;
+use crate::object::{HashTable, Lambda, Op, Params};
+use crate::{lexer, macros, number, parser};
 use crate::{object::Object, scope::Scope};
+use std::borrow::Cow;
 use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::rc::Rc;
 
-pub fn eval(string: String, scope: &mut Rc<RefCell<Scope>>) -> Result<Object, String> {
-    let mut lexer_tokens = lexer::lexing(&string);
+/// Evaluates the top-level forms of `src` that begin at byte offset `start` in
+/// order and returns the value of the last one. Spans in the
+/// result and in any error are relative to the whole of `src`, so forms kept
+/// around from earlier input still point at the right place.
+pub fn eval(src: &str, start: usize, scope: &mut Rc<RefCell<Scope>>) -> Result<Object, LispError> {
+    let mut lexer_tokens = lexer::lexing(&src[start..]);
+    for (_, span) in lexer_tokens.iter_mut() {
+        span.start += start;
+        span.end += start;
+    }
     lexer_tokens.reverse();
-    let parsed_objects = match parser::parse(&mut lexer_tokens) {
-        Err(s) => return Err(s.to_string()),
-        Ok(o) => o,
-    };
-    eval_obj(&parsed_objects, scope)
+    let forms = parser::parse(&mut lexer_tokens)?;
+
+    let mut result = Object::Void;
+    for (form, span) in &forms {
+        result = macros::macroexpand_all(form, scope)
+            .and_then(|form| eval_obj(&form, scope))
+            .map_err(|e| e.with_span(*span))?;
+    }
+    Ok(result)
 }
 
-fn eval_obj(obj: &Object, scope: &mut Rc<RefCell<Scope>>) -> Result<Object, String> {
-    match obj {
-        Object::List(l) => eval_list(l, scope),
-        Object::Void => Ok(Object::Void),
-        Object::Lambda(_params, _body) => Ok(Object::Void),
-        Object::Bool(_) => Ok(obj.clone()),
-        Object::Integer(n) => Ok(Object::Integer(*n)),
-        Object::Float(f) => Ok(Object::Float(*f)),
-        Object::Name(s) => eval_name(s, scope),
-        _ => Err("Unexpected eval type!".to_string()),
+/// Outcome of evaluating a list: either a finished value or a form in tail
+/// position that `eval_obj` should continue with instead of recursing.
+enum Step {
+    Done(Object),
+    Tail(Object, Rc<RefCell<Scope>>),
+}
+
+fn eval_obj(obj: &Object, scope: &mut Rc<RefCell<Scope>>) -> Result<Object, LispError> {
+    let mut obj = Cow::Borrowed(obj);
+    let mut scope = scope.clone();
+    loop {
+        let step = match obj.as_ref() {
+            Object::List(l, span) => eval_list(l, &mut scope).map_err(|e| e.with_span(*span))?,
+            Object::Void => return Ok(Object::Void),
+            Object::Lambda(_)
+            | Object::Macro(_)
+            | Object::Syntax(_)
+            | Object::Operator(_)
+            | Object::Builtin(_)
+            | Object::HashMap(_)
+            | Object::Vector(_)
+            | Object::Keyword(_)
+            | Object::BigInteger(_)
+            | Object::Rational(_)
+            | Object::Bool(_)
+            | Object::Char(_)
+            | Object::String(_) => return Ok(obj.into_owned()),
+            Object::Integer(n) => return Ok(Object::Integer(*n)),
+            Object::Float(f) => return Ok(Object::Float(*f)),
+            Object::Name(_) | Object::Alias(_) => return eval_name(&obj, &mut scope),
+            _ => {
+                return Err(LispError::invalid_form(format!(
+                    "Unexpected eval type {}",
+                    obj.type_name()
+                )))
+            }
+        };
+
+        match step {
+            Step::Done(o) => return Ok(o),
+            Step::Tail(next, next_scope) => {
+                obj = Cow::Owned(next);
+                scope = next_scope;
+            }
+        }
     }
 }
 
-fn eval_list(list: &Vec<Object>, scope: &mut Rc<RefCell<Scope>>) -> Result<Object, String> {
-    if list.len() < 1 {
-        return Err("Invalid List".to_string());
+fn eval_list(list: &[Object], scope: &mut Rc<RefCell<Scope>>) -> Result<Step, LispError> {
+    if list.is_empty() {
+        return Ok(Step::Done(Object::nil()));
     }
     let head = &list[0];
     match head {
         Object::Condition => {
             if list.len() != 4 {
-                return Err("Invalid number of arguments for If conditional check!".to_string());
+                return Err(LispError::arity("if", 3, list.len() - 1));
             }
 
-            let cond_obj = eval_obj(&list[1], scope)?;
-            let cond = match cond_obj {
-                Object::Bool(b) => b,
-                _ => return Err("Condition must be a Bool type!".to_string()),
-            };
-
-            if cond == true {
-                return eval_obj(&list[2], scope);
-            } else {
-                return eval_obj(&list[3], scope);
-            }
+            let cond = eval_condition(&list[1], scope)?;
+            let branch = if cond { &list[2] } else { &list[3] };
+            Ok(Step::Tail(branch.clone(), scope.clone()))
         }
 
-        Object::Keyword(s) => match s.as_str() {
+        Object::SpecialForm(s) => match s.as_str() {
             "def" => {
                 if list.len() != 3 {
-                    return Err("Invalid number of arguments for define. Must be 3!".to_string());
+                    return Err(LispError::arity("def", 2, list.len() - 1));
                 }
 
-                let name = &list[1].clone();
+                match list[1].name() {
+                    Some(s) => {
+                        let o = eval_obj(&list[2], scope)?;
+                        scope.borrow_mut().set(s, o);
+                        Ok(Step::Done(Object::Void))
+                    }
+                    None => Err(LispError::type_mismatch("Name", list[1].type_name())),
+                }
+            }
 
-                match name {
-                    Object::Name(s) => {
+            "set!" => {
+                if list.len() != 3 {
+                    return Err(LispError::arity("set!", 2, list.len() - 1));
+                }
+
+                match list[1].name() {
+                    Some(s) => {
                         let o = eval_obj(&list[2], scope)?;
-                        scope.borrow_mut().set(&s, o);
-                        return Ok(Object::Void);
+                        match macros::assign(&list[1], o, scope) {
+                            Ok(()) => Ok(Step::Done(Object::Void)),
+                            Err(_) => Err(LispError::unbound(s)),
+                        }
                     }
-                    _ => Err("Second argument must be a Name type!".to_string()),
+                    None => Err(LispError::type_mismatch("Name", list[1].type_name())),
                 }
             }
 
             "lambda" => {
-                let params = match &list[1] {
-                    Object::List(list) => {
-                        let mut params = Vec::new();
-                        for o in list {
-                            match o {
-                                Object::Name(s) => params.push(s.clone()),
-                                _ => {
-                                    return Err(
-                                        "Lambda parameter list type must be Name type!".to_string()
-                                    )
-                                }
-                            }
-                        }
-                        params
+                if list.len() < 3 {
+                    return Err(LispError::arity("lambda", "at least 2", list.len() - 1));
+                }
+                let lambda = make_lambda(&list[1], &list[2..], scope)?;
+                Ok(Step::Done(Object::Lambda(lambda)))
+            }
+
+            "defmacro" => {
+                if list.len() < 4 {
+                    return Err(LispError::arity("defmacro", "at least 3", list.len() - 1));
+                }
+
+                match list[1].name() {
+                    Some(s) => {
+                        let lambda = make_lambda(&list[2], &list[3..], scope)?;
+                        scope.borrow_mut().set(s, Object::Macro(lambda));
+                        Ok(Step::Done(Object::Void))
                     }
-                    _ => return Err("Lambda parameters is not a list!".to_string()),
+                    None => Err(LispError::type_mismatch("Name", list[1].type_name())),
+                }
+            }
+
+            "define-syntax" => {
+                if list.len() != 3 {
+                    return Err(LispError::arity("define-syntax", 2, list.len() - 1));
+                }
+
+                match list[1].name() {
+                    Some(s) => match eval_obj(&list[2], scope)? {
+                        o @ Object::Syntax(_) => {
+                            scope.borrow_mut().set(s, o);
+                            Ok(Step::Done(Object::Void))
+                        }
+                        o => Err(LispError::type_mismatch("Syntax", o.type_name())),
+                    },
+                    None => Err(LispError::type_mismatch("Name", list[1].type_name())),
+                }
+            }
+
+            "syntax-rules" => {
+                let rules = macros::parse_syntax_rules(&list[1..], scope)?;
+                Ok(Step::Done(Object::Syntax(Rc::new(rules))))
+            }
+
+            "macroexpand" | "macroexpand-1" => {
+                if list.len() != 2 {
+                    return Err(LispError::arity(s, 1, list.len() - 1));
+                }
+                let form = eval_obj(&list[1], scope)?;
+                let expanded = if s == "macroexpand" {
+                    macros::macroexpand(&form, scope)?
+                } else {
+                    macros::macroexpand_1(&form, scope)?.unwrap_or(form)
                 };
+                Ok(Step::Done(expanded))
+            }
+
+            "let" | "let*" | "letrec" => {
+                if list.len() < 2 {
+                    return Err(LispError::arity(s, "at least 1", list.len() - 1));
+                }
+
+                let bindings = parse_bindings(s, &list[1])?;
+                let mut new_scope = Rc::new(RefCell::new(Scope::extend(scope.clone())));
+                match s.as_str() {
+                    "let" => {
+                        for (name, init) in bindings {
+                            let o = eval_obj(init, scope)?;
+                            new_scope.borrow_mut().set(name, o);
+                        }
+                    }
+                    "let*" => {
+                        for (name, init) in bindings {
+                            let o = eval_obj(init, &mut new_scope)?;
+                            new_scope.borrow_mut().set(name, o);
+                        }
+                    }
+                    _ => {
+                        // Every name is visible to every init, so local
+                        // lambdas can call each other.
+                        for (name, _) in &bindings {
+                            new_scope.borrow_mut().set(name, Object::Void);
+                        }
+                        for (name, init) in bindings {
+                            let o = eval_obj(init, &mut new_scope)?;
+                            new_scope.borrow_mut().set(name, o);
+                        }
+                    }
+                }
+                eval_body(&list[2..], new_scope)
+            }
+
+            "begin" => eval_body(&list[1..], scope.clone()),
+
+            "when" | "unless" => {
+                if list.len() < 2 {
+                    return Err(LispError::arity(s, "at least 1", list.len() - 1));
+                }
+
+                let cond = eval_condition(&list[1], scope)?;
+                if cond == (s == "when") {
+                    eval_body(&list[2..], scope.clone())
+                } else {
+                    Ok(Step::Done(Object::Void))
+                }
+            }
+
+            "cond" => {
+                for clause in &list[1..] {
+                    let clause = match clause {
+                        Object::List(l, _) if !l.is_empty() => l,
+                        _ => {
+                            return Err(LispError::invalid_form(
+                                "cond clause must be (test body...)",
+                            ))
+                        }
+                    };
+                    if is_else(&clause[0]) {
+                        return eval_body(&clause[1..], scope.clone());
+                    }
+                    let test = eval_obj(&clause[0], scope)?;
+                    if !test.is_truthy() {
+                        continue;
+                    }
+                    if clause.len() == 1 {
+                        return Ok(Step::Done(test));
+                    }
+                    return eval_body(&clause[1..], scope.clone());
+                }
+                Ok(Step::Done(Object::Void))
+            }
 
-                let body = match &list[2] {
-                    Object::List(list) => list.clone(),
-                    _ => return Err("Lambda parameters is not a list!".to_string()),
+            "case" => {
+                if list.len() < 2 {
+                    return Err(LispError::arity("case", "at least 1", list.len() - 1));
+                }
+
+                let key = eval_obj(&list[1], scope)?;
+                for clause in &list[2..] {
+                    let (datums, body) = match clause {
+                        Object::List(l, _) if !l.is_empty() => (&l[0], &l[1..]),
+                        _ => {
+                            return Err(LispError::invalid_form(
+                                "case clause must be ((datum...) body...)",
+                            ))
+                        }
+                    };
+                    let matched = match datums {
+                        Object::List(datums, _) => datums.contains(&key),
+                        o if is_else(o) => true,
+                        o => return Err(LispError::type_mismatch("List", o.type_name())),
+                    };
+                    if matched {
+                        return eval_body(body, scope.clone());
+                    }
+                }
+                Ok(Step::Done(Object::Void))
+            }
+
+            "and" | "or" => {
+                // `and` stops at the first false value, `or` at the first true one.
+                let stop_on = s == "or";
+                let (last, init) = match list[1..].split_last() {
+                    Some(split) => split,
+                    None => return Ok(Step::Done(Object::Bool(!stop_on))),
                 };
+                for form in init {
+                    let o = eval_obj(form, scope)?;
+                    if o.is_truthy() == stop_on {
+                        return Ok(Step::Done(o));
+                    }
+                }
+                Ok(Step::Tail(last.clone(), scope.clone()))
+            }
 
-                Ok(Object::Lambda(params, body))
+            "quote" => {
+                if list.len() != 2 {
+                    return Err(LispError::arity("quote", 1, list.len() - 1));
+                }
+                Ok(Step::Done(quote(&list[1])?))
             }
 
-            _ => {
-                return Err("Invalid keyword!".to_string());
+            "quasiquote" => {
+                if list.len() != 2 {
+                    return Err(LispError::arity("quasiquote", 1, list.len() - 1));
+                }
+                Ok(Step::Done(quasiquote(&list[1], 1, scope)?))
             }
-        },
 
-        Object::Operator(_) => {
-            return eval_operator(&head, list, scope);
-        }
+            // What `#(a b ...)` reads as.
+            "vector" => {
+                let elements = list[1..]
+                    .iter()
+                    .map(|o| eval_obj(o, scope))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Step::Done(Object::Vector(Rc::new(RefCell::new(elements)))))
+            }
 
-        Object::Name(s) => {
-            return eval_lambda_call(s, scope, list);
-        }
+            // What `{k1 v1 ...}` reads as; the parser checks that every key
+            // has a value.
+            "hash-map" => {
+                let elements = list[1..]
+                    .iter()
+                    .map(|o| eval_obj(o, scope))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Step::Done(Object::HashMap(HashTable::from_pairs(
+                    &elements,
+                )?)))
+            }
+
+            "unquote" | "unquote-splicing" => Err(LispError::invalid_form(format!(
+                "{} outside of quasiquote",
+                s
+            ))),
+
+            _ => Err(LispError::invalid_form(format!("Unknown keyword {}", s))),
+        },
 
         _ => {
-            let mut new_list = Vec::new();
-            for obj in list {
-                let result = eval_obj(obj, scope)?;
-                match result {
-                    Object::Void => {}
-                    _ => new_list.push(result),
+            let func = eval_obj(head, scope)?;
+            if let Object::Macro(_) | Object::Syntax(_) = &func {
+                // Macros that were not expanded ahead of time, e.g. because
+                // they were defined after the code using them was read.
+                let expansion = macros::expand(&func, list)?;
+                return Ok(Step::Tail(expansion, scope.clone()));
+            }
+
+            let mut args = Vec::new();
+            for obj in &list[1..] {
+                args.push(eval_obj(obj, scope)?);
+            }
+            match func {
+                Object::Lambda(lambda) => {
+                    let name = macros::base_name(head).unwrap_or("lambda");
+                    call_lambda(&lambda, name, &args)
                 }
+                _ => apply(&func, &args).map(Step::Done),
             }
-            Ok(Object::List(new_list))
         }
     }
 }
 
-fn eval_lambda_call(
-    s: &String,
-    scope: &mut Rc<RefCell<Scope>>,
-    list: &Vec<Object>,
-) -> Result<Object, String> {
-    let lamdba = scope.borrow_mut().get(s);
-    if lamdba.is_some() {
-        match lamdba.unwrap() {
-            Object::Lambda(params, body) => {
-                let mut new_scope = Rc::new(RefCell::new(Scope::extend(scope.clone())));
-                for (i, param) in params.iter().enumerate() {
-                    let obj = eval_obj(&list[1 + i], scope)?;
-                    new_scope.borrow_mut().set(param, obj);
-                }
-                return eval_obj(&Object::List(body), &mut new_scope);
-            }
-            _ => return Err(format!("Unbound symbol: {} !", s)),
+/// Turns quoted data into a value. Lists stay lists, but vector and hash map
+/// literals inside them become vectors and hash maps.
+fn quote(datum: &Object) -> Result<Object, LispError> {
+    match datum {
+        Object::List(items, span) => {
+            let items = items.iter().map(quote).collect::<Result<Vec<_>, _>>()?;
+            literal(items, *span)
         }
-    } else {
-        return Err(format!("Unbound symbol: {} !", s));
+        o => Ok(symbol(o)),
     }
 }
 
-fn eval_name(s: &String, scope: &mut Rc<RefCell<Scope>>) -> Result<Object, String> {
-    let obj = scope.borrow_mut().get(s);
-    if obj.is_none() {
-        return Err("There is no defined name in this environment!".to_string());
+/// Quoted identifiers are plain symbols, also when `syntax-rules` renamed
+/// them in a template.
+fn symbol(obj: &Object) -> Object {
+    match macros::base_name(obj) {
+        Some(name) if matches!(obj, Object::Alias(_)) => Object::Name(name.to_string()),
+        _ => obj.clone(),
     }
+}
 
-    Ok(obj.unwrap().clone())
+/// Builds the value of a quoted list whose elements are already values,
+/// which for a vector or hash map literal is the vector or hash map itself.
+fn literal(mut items: Vec<Object>, span: Span) -> Result<Object, LispError> {
+    match items.first() {
+        Some(Object::SpecialForm(k)) if k == "vector" => {
+            items.remove(0);
+            Ok(Object::Vector(Rc::new(RefCell::new(items))))
+        }
+        Some(Object::SpecialForm(k)) if k == "hash-map" => {
+            if !items[1..].len().is_multiple_of(2) {
+                return Err(LispError::invalid_form(
+                    "Hash map literal needs a value for every key",
+                ));
+            }
+            Ok(Object::HashMap(HashTable::from_pairs(&items[1..])?))
+        }
+        _ => Ok(Object::List(items.into(), span)),
+    }
 }
 
-fn eval_operator(
-    object: &Object,
-    list: &Vec<Object>,
+/// Builds the value of a quasiquote template. `depth` counts the enclosing
+/// quasiquotes so that only unquotes belonging to the outermost one are
+/// evaluated.
+fn quasiquote(
+    template: &Object,
+    depth: usize,
     scope: &mut Rc<RefCell<Scope>>,
-) -> Result<Object, String> {
-    match object {
-        Object::Operator(operator) => {
-            let other_operands = list[1..].to_vec();
-
-            let mut operands: Vec<Object> = Vec::new();
-            for obj in other_operands {
-                let operand = eval_obj(&obj, scope)?;
-                operands.push(operand.clone());
-            }
-
-            let first_operand = &operands[0];
-            if !operands.iter().all(|_obj| matches!(first_operand, _obj)) {
-                return Err("Operands are not the same type!".to_string());
-            } else {
-                match operator {
-                    Op::Add => match first_operand {
-                        Object::Integer(n) => {
-                            let mut sum: i64 = *n;
-                            for i in 1..operands.len() {
-                                match operands[i] {
-                                    Object::Integer(n) => sum += n,
-                                    _ => return Err("+ Operator Int Error!".to_string()),
-                                }
-                            }
-                            return Ok(Object::Integer(sum));
-                        }
-                        Object::Float(f) => {
-                            let mut sum: f64 = *f;
-                            for i in 1..operands.len() {
-                                match operands[i] {
-                                    Object::Float(f) => sum += f,
-                                    _ => return Err("+ Operator Float Error!".to_string()),
-                                }
-                            }
-                            return Ok(Object::Float(sum));
-                        }
-                        _ => {
-                            return Err(
-                                "+ Operator not implemented for this object type!".to_string()
-                            )
-                        }
-                    },
-
-                    Op::Sub => match first_operand {
-                        Object::Integer(n) => {
-                            let mut diff: i64 = *n;
-                            for i in 1..operands.len() {
-                                match operands[i] {
-                                    Object::Integer(n) => diff -= n,
-                                    _ => return Err("- Operator Int Error!".to_string()),
-                                }
-                            }
-                            return Ok(Object::Integer(diff));
-                        }
-                        Object::Float(f) => {
-                            let mut diff: f64 = *f;
-                            for i in 1..operands.len() {
-                                match operands[i] {
-                                    Object::Float(f) => diff -= f,
-                                    _ => return Err("- Operator Float Error!".to_string()),
-                                }
-                            }
-                            return Ok(Object::Float(diff));
-                        }
-                        _ => {
-                            return Err(
-                                "- Operator not implemented for this object type!".to_string()
-                            )
-                        }
-                    },
+) -> Result<Object, LispError> {
+    let (items, tail, span) = match template {
+        Object::List(items, span) => (Cow::Borrowed(items.as_slice()), None, *span),
+        Object::Pair(_) => {
+            let (items, tail) = template.split_tail();
+            (Cow::Owned(items), Some(tail), Span::default())
+        }
+        o => return Ok(symbol(o)),
+    };
 
-                    Op::Mul => match first_operand {
-                        Object::Integer(n) => {
-                            let mut mult: i64 = *n;
-                            for i in 1..operands.len() {
-                                match operands[i] {
-                                    Object::Integer(n) => mult *= n,
-                                    _ => return Err("* Operator Int Error!".to_string()),
-                                }
-                            }
-                            return Ok(Object::Integer(mult));
-                        }
-                        Object::Float(f) => {
-                            let mut mult: f64 = *f;
-                            for i in 1..operands.len() {
-                                match operands[i] {
-                                    Object::Float(f) => mult *= f,
-                                    _ => return Err("* Operator Float Error!".to_string()),
-                                }
-                            }
-                            return Ok(Object::Float(mult));
-                        }
-                        _ => {
-                            return Err(
-                                "* Operator not implemented for this object type!".to_string()
-                            )
-                        }
-                    },
+    if let (None, [Object::SpecialForm(k), x]) = (&tail, &*items) {
+        match k.as_str() {
+            "unquote" if depth == 1 => return eval_obj(x, scope),
+            // Splices are handled by the enclosing list below.
+            "unquote-splicing" if depth == 1 => {
+                return Err(LispError::invalid_form("unquote-splicing outside a list"))
+            }
+            "unquote" | "unquote-splicing" => {
+                let inner = quasiquote(x, depth - 1, scope)?;
+                return Ok(Object::List(vec![items[0].clone(), inner].into(), span));
+            }
+            "quasiquote" => {
+                let inner = quasiquote(x, depth + 1, scope)?;
+                return Ok(Object::List(vec![items[0].clone(), inner].into(), span));
+            }
+            _ => {}
+        }
+    }
 
-                    Op::Div => match first_operand {
-                        Object::Integer(n) => {
-                            let mut diff: i64 = *n;
-                            for i in 1..operands.len() {
-                                match operands[i] {
-                                    Object::Integer(n) => diff /= n,
-                                    _ => return Err("/ Operator Int Error!".to_string()),
-                                }
-                            }
-                            return Ok(Object::Integer(diff));
-                        }
-                        Object::Float(f) => {
-                            let mut diff: f64 = *f;
-                            for i in 1..operands.len() {
-                                match operands[i] {
-                                    Object::Float(f) => diff /= f,
-                                    _ => return Err("/ Operator Float Error!".to_string()),
-                                }
-                            }
-                            return Ok(Object::Float(diff));
-                        }
-                        _ => {
-                            return Err(
-                                "/ Operator not implemented for this object type!".to_string()
-                            )
-                        }
-                    },
+    // `(a . ,x)` is read as `(a unquote x)`, so a trailing unquote is the
+    // template's tail.
+    let (items, tail) = match (&*items, tail) {
+        ([init @ .., Object::SpecialForm(k), x], None)
+            if depth == 1 && k == "unquote" && !init.is_empty() =>
+        {
+            (init, Some(eval_obj(x, scope)?))
+        }
+        (items, tail) => {
+            let tail = match tail {
+                Some(tail) => Some(quasiquote(&tail, depth, scope)?),
+                None => None,
+            };
+            (items, tail)
+        }
+    };
 
-                    Op::Eq => {
-                        let mut res = false;
-                        for i in 1..operands.len() {
-                            if operands[i] != *first_operand {
-                                return Ok(Object::Bool(false));
-                            } else {
-                                res = true;
-                            }
-                        }
-                        return Ok(Object::Bool(res));
+    let mut res = Vec::new();
+    for item in items {
+        match item {
+            Object::List(l, _) if depth == 1 => match l.as_slice() {
+                [Object::SpecialForm(k), x] if k == "unquote-splicing" => {
+                    let spliced = eval_obj(x, scope)?;
+                    match spliced.list_items() {
+                        Some(items) => res.extend_from_slice(&items),
+                        None => return Err(LispError::type_mismatch("List", spliced.type_name())),
                     }
+                }
+                _ => res.push(quasiquote(item, depth, scope)?),
+            },
+            _ => res.push(quasiquote(item, depth, scope)?),
+        }
+    }
+    match tail {
+        Some(tail) => Ok(Object::dotted(res, tail, span)),
+        // Nested templates are still code, so only the outermost one builds
+        // vectors.
+        None if depth == 1 => literal(res, span),
+        None => Ok(Object::List(res.into(), span)),
+    }
+}
 
-                    Op::NotEq => {
-                        let mut res = false;
-                        for i in 1..operands.len() {
-                            if operands[i] == *first_operand {
-                                res |= false;
-                            } else {
-                                res |= true;
-                            }
-                        }
-                        return Ok(Object::Bool(res));
-                    }
+fn make_lambda(
+    params: &Object,
+    body: &[Object],
+    scope: &Rc<RefCell<Scope>>,
+) -> Result<Lambda, LispError> {
+    let params = match params {
+        Object::List(list, _) => parse_params(list)?,
+        // `(a b . rest)` is read as pairs and means `(a b &rest rest)`.
+        Object::Pair(_) => {
+            let (list, rest) = params.split_tail();
+            let mut params = parse_params(&list)?;
+            match (rest.name(), &params.rest) {
+                (Some(s), None) => params.rest = Some(s.to_string()),
+                (Some(_), Some(_)) => {
+                    return Err(LispError::invalid_form(
+                        "Only one &rest parameter is allowed",
+                    ))
+                }
+                (None, _) => return Err(LispError::type_mismatch("Name", rest.type_name())),
+            }
+            params
+        }
+        o => return Err(LispError::type_mismatch("List", o.type_name())),
+    };
 
-                    Op::Greater => {
-                        let mut res = false;
-                        for i in 1..operands.len() {
-                            if *first_operand > operands[i] {
-                                res = true;
-                            } else {
-                                return Ok(Object::Bool(false));
-                            }
-                        }
-                        return Ok(Object::Bool(res));
-                    }
+    Ok(Lambda {
+        params,
+        body: body.into(),
+        env: scope.clone(),
+    })
+}
 
-                    Op::Smaller => {
-                        let mut res = false;
-                        for i in 1..operands.len() {
-                            if *first_operand < operands[i] {
-                                res = true;
-                            } else {
-                                return Ok(Object::Bool(false));
-                            }
-                        }
-                        return Ok(Object::Bool(res));
+fn parse_params(list: &[Object]) -> Result<Params, LispError> {
+    enum Section {
+        Required,
+        Optional,
+        Rest,
+    }
+
+    let mut params = Params::default();
+    let mut section = Section::Required;
+    for o in list {
+        match (o.name(), o, &section) {
+            (Some("&optional"), _, _) => match section {
+                Section::Required => section = Section::Optional,
+                _ => return Err(LispError::invalid_form("Misplaced &optional")),
+            },
+            (Some("&rest"), _, _) => match section {
+                Section::Rest => return Err(LispError::invalid_form("Misplaced &rest")),
+                _ => section = Section::Rest,
+            },
+            (Some(s), _, Section::Required) => params.required.push(s.to_string()),
+            (Some(s), _, Section::Optional) => params.optional.push((s.to_string(), None)),
+            (None, Object::List(l, _), Section::Optional) => {
+                match (l.as_slice(), l.first().and_then(Object::name)) {
+                    ([_, default], Some(s)) => {
+                        params.optional.push((s.to_string(), Some(default.clone())))
                     }
+                    _ => {
+                        return Err(LispError::invalid_form(
+                            "Optional parameter must be (name default)",
+                        ))
+                    }
+                }
+            }
+            (Some(s), _, Section::Rest) => {
+                if params.rest.is_some() {
+                    return Err(LispError::invalid_form(
+                        "Only one &rest parameter is allowed",
+                    ));
                 }
+                params.rest = Some(s.to_string());
+            }
+            _ => return Err(LispError::type_mismatch("Name", o.type_name())),
+        }
+    }
+
+    if matches!(section, Section::Rest) && params.rest.is_none() {
+        return Err(LispError::invalid_form("Missing parameter after &rest"));
+    }
+    Ok(params)
+}
+
+/// Calls a procedure value with already evaluated arguments.
+pub fn apply(func: &Object, args: &[Object]) -> Result<Object, LispError> {
+    apply_named(func, "lambda", args)
+}
+
+/// `apply` for a procedure called by `name`, which arity errors report.
+pub fn apply_named(func: &Object, name: &str, args: &[Object]) -> Result<Object, LispError> {
+    match func {
+        Object::Lambda(lambda) => match call_lambda(lambda, name, args)? {
+            Step::Done(o) => Ok(o),
+            Step::Tail(form, mut scope) => eval_obj(&form, &mut scope),
+        },
+        Object::Operator(operator) => eval_operator(operator, args),
+        Object::Builtin(builtin) => (builtin.func)(args),
+        o => Err(LispError::type_mismatch("Procedure", o.type_name())),
+    }
+}
+
+/// Binds the arguments and evaluates all but the last body form, which is
+/// handed back in tail position.
+fn call_lambda(lambda: &Lambda, name: &str, args: &[Object]) -> Result<Step, LispError> {
+    let params = &lambda.params;
+    let (min, max) = params.arity();
+    if args.len() < min || max.is_some_and(|max| args.len() > max) {
+        let expected = match max {
+            Some(max) if max == min => min.to_string(),
+            Some(max) => format!("{} to {}", min, max),
+            None => format!("at least {}", min),
+        };
+        return Err(LispError::arity(name, expected, args.len()));
+    }
+
+    let mut new_scope = Rc::new(RefCell::new(Scope::extend(lambda.env.clone())));
+    for (param, obj) in params.required.iter().zip(args) {
+        new_scope.borrow_mut().set(param, obj.clone());
+    }
+    let mut rest_args = args.iter().skip(min);
+    for (param, default) in &params.optional {
+        let obj = match (rest_args.next(), default) {
+            (Some(obj), _) => obj.clone(),
+            (None, Some(default)) => eval_obj(default, &mut new_scope)?,
+            (None, None) => Object::Void,
+        };
+        new_scope.borrow_mut().set(param, obj);
+    }
+    if let Some(param) = &params.rest {
+        let rest = Object::List(rest_args.cloned().collect(), Span::default());
+        new_scope.borrow_mut().set(param, rest);
+    }
+
+    eval_body(&lambda.body, new_scope)
+}
+
+fn eval_condition(obj: &Object, scope: &mut Rc<RefCell<Scope>>) -> Result<bool, LispError> {
+    Ok(eval_obj(obj, scope)?.is_truthy())
+}
+
+/// `else` in `cond` and `case`, also when it was introduced by a macro.
+fn is_else(obj: &Object) -> bool {
+    macros::base_name(obj) == Some("else")
+}
+
+/// Evaluates all but the last form of a body, which is handed back in tail
+/// position.
+fn eval_body(body: &[Object], mut scope: Rc<RefCell<Scope>>) -> Result<Step, LispError> {
+    match body.split_last() {
+        Some((last, init)) => {
+            for form in init {
+                eval_obj(form, &mut scope)?;
             }
+            Ok(Step::Tail(last.clone(), scope))
+        }
+        None => Ok(Step::Done(Object::Void)),
+    }
+}
+
+/// Reads the `((name init) ...)` list of a `let` form.
+fn parse_bindings<'a>(
+    form: &str,
+    bindings: &'a Object,
+) -> Result<Vec<(&'a str, &'a Object)>, LispError> {
+    let list = match bindings {
+        Object::List(l, _) => l,
+        o => return Err(LispError::type_mismatch("List", o.type_name())),
+    };
+
+    let mut res = Vec::new();
+    for binding in list {
+        match binding {
+            Object::List(l, _) => match (l.as_slice(), l.first().and_then(Object::name)) {
+                ([_, init], Some(name)) => res.push((name, init)),
+                _ => {
+                    return Err(LispError::invalid_form(format!(
+                        "{} binding must be (name value)",
+                        form
+                    )))
+                }
+            },
+            o => return Err(LispError::type_mismatch("List", o.type_name())),
         }
-        _ => Err("Not an operator!".to_string()),
+    }
+    Ok(res)
+}
+
+fn eval_name(ident: &Object, scope: &mut Rc<RefCell<Scope>>) -> Result<Object, LispError> {
+    macros::lookup(ident, scope).ok_or_else(|| LispError::unbound(&ident.to_string()))
+}
+
+fn eval_operator(operator: &Op, operands: &[Object]) -> Result<Object, LispError> {
+    if operands.is_empty() {
+        return Err(LispError::arity(&operator.to_string(), "at least 1", 0));
+    }
+
+    let first_operand = &operands[0];
+    let rest = &operands[1..];
+    match operator {
+        Op::Add => fold_numbers(first_operand, rest, number::add),
+        Op::Sub => fold_numbers(first_operand, rest, number::sub),
+        Op::Mul => fold_numbers(first_operand, rest, number::mul),
+        Op::Div => fold_numbers(first_operand, rest, number::div),
+
+        Op::Eq => Ok(Object::Bool(
+            !rest.is_empty() && rest.iter().all(|o| number::equal(first_operand, o)),
+        )),
+
+        Op::NotEq => Ok(Object::Bool(
+            rest.iter().any(|o| !number::equal(first_operand, o)),
+        )),
+
+        Op::Greater => compare_all(first_operand, rest, |ord| ord == Ordering::Greater),
+        Op::GreaterEq => compare_all(first_operand, rest, |ord| ord != Ordering::Less),
+        Op::Smaller => compare_all(first_operand, rest, |ord| ord == Ordering::Less),
+        Op::SmallerEq => compare_all(first_operand, rest, |ord| ord != Ordering::Greater),
+    }
+}
+
+fn fold_numbers(
+    first: &Object,
+    rest: &[Object],
+    op: fn(&Object, &Object) -> Result<Object, LispError>,
+) -> Result<Object, LispError> {
+    if !number::is_number(first) {
+        return Err(LispError::type_mismatch("Number", first.type_name()));
+    }
+    rest.iter().try_fold(first.clone(), |acc, o| op(&acc, o))
+}
+
+/// Checks `first` against every other operand. Numbers of either kind
+/// compare by value, strings compare lexicographically and characters by
+/// code point.
+fn compare_all(
+    first: &Object,
+    rest: &[Object],
+    accept: fn(Ordering) -> bool,
+) -> Result<Object, LispError> {
+    if rest.is_empty() {
+        return Ok(Object::Bool(false));
+    }
+    for o in rest {
+        let ord = match (first, o) {
+            (Object::String(a), Object::String(b)) => Some(a.cmp(b)),
+            (Object::Char(a), Object::Char(b)) => Some(a.cmp(b)),
+            _ => number::compare(first, o)?,
+        };
+        if !ord.is_some_and(accept) {
+            return Ok(Object::Bool(false));
+        }
+    }
+    Ok(Object::Bool(true))
+}
+
+#[cfg(test)]
+mod eval_tests {
+    use super::*;
+    use crate::builtins;
+    use crate::error::ErrorKind;
+
+    fn run(src: &str) -> Result<Object, LispError> {
+        let mut scope = Rc::new(RefCell::new(Scope::new()));
+        builtins::register(&mut scope.borrow_mut());
+        eval(src, 0, &mut scope)
+    }
+
+    /// Runs `src` and prints the result the way the REPL would.
+    fn show(src: &str) -> String {
+        run(src).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_closure_captures_defining_scope() {
+        let src = "(def make-adder (lambda (n) (lambda (x) (+ x n))))
+                   (def add5 (make-adder 5))
+                   (def n 100)
+                   (add5 10)";
+        assert_eq!(run(src).unwrap(), Object::Integer(15));
+    }
+
+    #[test]
+    fn test_call_arbitrary_callable_head() {
+        assert_eq!(
+            run("((lambda (x) (* x x)) 5)").unwrap(),
+            Object::Integer(25)
+        );
+        assert_eq!(
+            run("(((lambda (n) (lambda (x) (+ x n))) 1) 2)").unwrap(),
+            Object::Integer(3)
+        );
+        assert_eq!(run("(def plus +) (plus 1 2)").unwrap(), Object::Integer(3));
+    }
+
+    #[test]
+    fn test_lambda_arity_and_parameter_kinds() {
+        assert_eq!(
+            run("((lambda (a b) a) 1)").unwrap_err().kind,
+            ErrorKind::Arity {
+                name: "lambda".to_string(),
+                expected: "2".to_string(),
+                found: 1
+            }
+        );
+        assert!(run("((lambda (a) a) 1 2)").is_err());
+        assert_eq!(
+            run("((lambda (a &optional (b (+ a 1))) (+ a b)) 1)").unwrap(),
+            Object::Integer(3)
+        );
+        assert_eq!(
+            run("((lambda (a &rest more) more) 1 2 3)").unwrap(),
+            Object::List(
+                vec![Object::Integer(2), Object::Integer(3)].into(),
+                Span::default()
+            )
+        );
+        assert_eq!(show("((lambda (a . rest) rest) 1 2 3)"), "(2 3)");
+        assert_eq!(
+            show("(defmacro m (a . rest) `(list ,@rest)) (m 1 2 3)"),
+            "(2 3)"
+        );
+        assert!(run("(lambda (a &rest b . c) c)").is_err());
+        assert_eq!(
+            run("(def f (lambda (a) a)) (f)").unwrap_err().kind,
+            ErrorKind::Arity {
+                name: "f".to_string(),
+                expected: "1".to_string(),
+                found: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_tail_calls_run_in_constant_stack() {
+        let src = "(def count (lambda (n acc) (if (== n 0) acc (count (- n 1) (+ acc 1)))))
+                   (count 100000 0)";
+        assert_eq!(run(src).unwrap(), Object::Integer(100000));
+    }
+
+    #[test]
+    fn test_multiple_top_level_forms() {
+        assert_eq!(
+            run("(def x 2) (def y 3) (* x y)").unwrap(),
+            Object::Integer(6)
+        );
+        assert_eq!(run("").unwrap(), Object::Void);
+        assert!(matches!(
+            run("(def x 2))").unwrap_err().kind,
+            ErrorKind::Parse(_)
+        ));
+    }
+
+    #[test]
+    fn test_bare_atoms_at_top_level() {
+        assert_eq!(run("42").unwrap(), Object::Integer(42));
+        assert_eq!(run("(def x 1.5) x").unwrap(), Object::Float(1.5));
+        assert_eq!(run("#t").unwrap(), Object::Bool(true));
+    }
+
+    #[test]
+    fn test_strings() {
+        let s = |v: &str| Object::String(v.to_string());
+        assert_eq!(run(r#""a\tb\n""#).unwrap(), s("a\tb\n"));
+        assert_eq!(
+            run(r#"(string-append "foo" "-" "bar")"#).unwrap(),
+            s("foo-bar")
+        );
+        assert_eq!(
+            run(r#"(string-length "héllo")"#).unwrap(),
+            Object::Integer(5)
+        );
+        assert_eq!(run(r#"(substring "hello" 1 3)"#).unwrap(), s("el"));
+        assert_eq!(
+            run(r#"(string-contains "hello" "lo")"#).unwrap(),
+            Object::Integer(3)
+        );
+        assert_eq!(run(r#"(string-upcase "abc")"#).unwrap(), s("ABC"));
+        assert_eq!(
+            run(r#"(string->number "2.5")"#).unwrap(),
+            Object::Float(2.5)
+        );
+        assert_eq!(run(r#"(number->string 42)"#).unwrap(), s("42"));
+        assert!(matches!(
+            run(r#"(substring "abc" 1 5)"#).unwrap_err().kind,
+            ErrorKind::IndexOutOfBounds { index: 5, len: 3 }
+        ));
+    }
+
+    #[test]
+    fn test_list_values() {
+        let ints = |v: &[i64]| {
+            Object::List(
+                v.iter().map(|n| Object::Integer(*n)).collect(),
+                Span::default(),
+            )
+        };
+        assert_eq!(run("(quote (1 2 3))").unwrap(), ints(&[1, 2, 3]));
+        assert_eq!(run("(cons 1 (list 2 3))").unwrap(), ints(&[1, 2, 3]));
+        assert_eq!(run("(car (list 1 2))").unwrap(), Object::Integer(1));
+        assert_eq!(run("(cdr (list 1 2))").unwrap(), ints(&[2]));
+        assert_eq!(run("(null? (cdr (list 1)))").unwrap(), Object::Bool(true));
+        assert_eq!(run("(length (list 1 2))").unwrap(), Object::Integer(2));
+        assert_eq!(
+            run("(reverse (append (list 1) (list 2 3)))").unwrap(),
+            ints(&[3, 2, 1])
+        );
+        assert_eq!(
+            run("(list 1 (list 2.5 \"a\"))").unwrap().to_string(),
+            "(1 (2.5 \"a\"))"
+        );
+        assert!(matches!(
+            run("(1 2 3)").unwrap_err().kind,
+            ErrorKind::TypeMismatch { .. }
+        ));
+    }
+
+    #[test]
+    fn test_cons_cells() {
+        assert_eq!(run("(cons 1 2)").unwrap().to_string(), "(1 . 2)");
+        assert_eq!(run("(cons 1 (cons 2 3))").unwrap().to_string(), "(1 2 . 3)");
+        assert_eq!(run("(cdr '(1 . 2))").unwrap(), Object::Integer(2));
+        assert_eq!(run("'(1 . (2 3))").unwrap().to_string(), "(1 2 3)");
+        assert_eq!(run("(pair? (cons 1 2))").unwrap(), Object::Bool(true));
+        assert_eq!(run("(pair? '())").unwrap(), Object::Bool(false));
+        assert!(run("(length (cons 1 2))").is_err());
+        assert!(run("'(. 1)").is_err());
+
+        // Building with `cons` and walking with `cdr` shares the tails, so a
+        // long list takes linear time.
+        let src = "(def build (lambda (n acc) (if (== n 0) acc (build (- n 1) (cons n acc)))))
+                   (def sum (lambda (l acc) (if (null? l) acc (sum (cdr l) (+ acc (car l))))))
+                   (def l (build 20000 '()))
+                   (list (length l) (sum l 0) (== l (cdr (cons 0 l))))";
+        assert_eq!(run(src).unwrap().to_string(), "(20000 200010000 true)");
+    }
+
+    #[test]
+    fn test_quote_and_quasiquote() {
+        assert_eq!(run("'(1 two)").unwrap().to_string(), "(1 two)");
+        assert_eq!(run("(car ''a)").unwrap().to_string(), "quote");
+        assert_eq!(
+            run("(def x 2) (def l '(3 4)) `(1 ,x ,@l (nested ,(+ x 1)))")
+                .unwrap()
+                .to_string(),
+            "(1 2 3 4 (nested 3))"
+        );
+        assert_eq!(
+            run("(def x 2) `(a `(b ,(c ,x)))").unwrap().to_string(),
+            "(a (quasiquote (b (unquote (c 2)))))"
+        );
+        assert!(run(",x").is_err());
+        assert!(run("(def x (list 1)) `,@x").is_err());
+        assert!(run("(def x (list 1)) `,@,x").is_err());
+
+        // Unquote and splices also work around a dotted tail.
+        assert_eq!(show("(def x 2) `(1 . ,x)"), "(1 . 2)");
+        assert_eq!(show("(def x '(2 3)) `(1 . ,x)"), "(1 2 3)");
+        assert_eq!(show("`(1 ,@'(2) . 3)"), "(1 2 . 3)");
+    }
+
+    #[test]
+    fn test_defmacro() {
+        let src = "(defmacro my-unless (c &rest body) `(if ,c #f ((lambda () ,@body))))
+                   (def x 1)
+                   (my-unless (== x 2) (def y 10) (+ x y))";
+        assert_eq!(run(src).unwrap(), Object::Integer(11));
+
+        let src = "(defmacro twice (e) `(+ ,e ,e))
+                   (defmacro quad (e) `(twice (twice ,e)))
+                   (quad 1)";
+        assert_eq!(run(src).unwrap(), Object::Integer(4));
+
+        let src = "(defmacro twice (e) `(+ ,e ,e))
+                   (defmacro quad (e) `(twice (twice ,e)))";
+        assert_eq!(
+            run(&format!("{} (macroexpand-1 '(quad x))", src))
+                .unwrap()
+                .to_string(),
+            "(twice (twice x))"
+        );
+        assert_eq!(
+            run(&format!("{} (macroexpand '(quad x))", src))
+                .unwrap()
+                .to_string(),
+            "(+ (twice x) (twice x))"
+        );
+
+        // A local binding shadows a macro of the same name.
+        let src = "(defmacro m (x) x)";
+        assert_eq!(
+            run(&format!("{} ((lambda (m) (m 1 2)) list)", src))
+                .unwrap()
+                .to_string(),
+            "(1 2)"
+        );
+        assert_eq!(
+            run(&format!("{} (let ((m list)) (m 1 2))", src))
+                .unwrap()
+                .to_string(),
+            "(1 2)"
+        );
+        assert_eq!(
+            run(&format!("{} (let ((f (lambda () (m 3)))) (f))", src)).unwrap(),
+            Object::Integer(3)
+        );
+    }
+
+    #[test]
+    fn test_syntax_rules() {
+        let src = "(define-syntax my-list
+                     (syntax-rules ()
+                       ((_ (a b) ...) (list (+ a b) ...))))
+                   (my-list (1 2) (3 4))";
+        assert_eq!(run(src).unwrap().to_string(), "(3 7)");
+
+        let src = "(define-syntax pick
+                     (syntax-rules (else)
+                       ((_ else e) e)
+                       ((_ c e) c)))
+                   (list (pick else 1) (pick 2 3))";
+        assert_eq!(run(src).unwrap().to_string(), "(1 2)");
+
+        let src = "(define-syntax my-let
+                     (syntax-rules ()
+                       ((_ ((name val) ...) body ...) ((lambda (name ...) body ...) val ...))))
+                   (my-let ((a 1) (b 2)) (+ a b))";
+        assert_eq!(run(src).unwrap(), Object::Integer(3));
+        assert!(run("(syntax-rules () ((_ ... a) a))").is_err());
+        assert!(run("(syntax-rules () ((_ (... a)) a))").is_err());
+    }
+
+    #[test]
+    fn test_syntax_rules_hygiene() {
+        // The template's `t` must not capture the user's `t`.
+        let src = "(define-syntax my-or
+                     (syntax-rules ()
+                       ((_) #f)
+                       ((_ e) e)
+                       ((_ e r ...) ((lambda (t) (if t t (my-or r ...))) e))))
+                   (def t 5)
+                   (my-or #f t)";
+        assert_eq!(run(src).unwrap(), Object::Integer(5));
+
+        // A local `list` at the use site must not capture the template's `list`.
+        let src = "(define-syntax make-pair
+                     (syntax-rules () ((_ a b) (list a b))))
+                   ((lambda (list) (make-pair 1 list)) 2)";
+        assert_eq!(run(src).unwrap().to_string(), "(1 2)");
+
+        // `set!` on a free identifier of the template updates the binding
+        // the macro sees.
+        let src = "(define-syntax bump! (syntax-rules () ((_ x) (set! counter (+ counter x)))))
+                   (def counter 0)
+                   ((lambda (counter) (bump! 2)) 10)
+                   counter";
+        assert_eq!(run(src).unwrap(), Object::Integer(2));
+
+        // Template symbols under quasiquote are data, not renamed identifiers.
+        let src = "(define-syntax m (syntax-rules () ((_ x) `(a ,x))))
+                   (m 1)";
+        assert_eq!(run(src).unwrap().to_string(), "(a 1)");
+        let src = "(define-syntax same (syntax-rules () ((_) (eq? 'a `a))))
+                   (same)";
+        assert_eq!(run(src).unwrap(), Object::Bool(true));
+    }
+
+    #[test]
+    fn test_let_forms() {
+        assert_eq!(
+            run("(def x 1) (let ((x 2) (y x)) (+ x y))").unwrap(),
+            Object::Integer(3)
+        );
+        assert_eq!(
+            run("(let* ((x 2) (y (* x 10))) (+ x y))").unwrap(),
+            Object::Integer(22)
+        );
+        let src = "(letrec ((even (lambda (n) (if (== n 0) #t (odd (- n 1)))))
+                            (odd (lambda (n) (if (== n 0) #f (even (- n 1))))))
+                     (even 10))";
+        assert_eq!(run(src).unwrap(), Object::Bool(true));
+        assert!(matches!(
+            run("(let ((tmp 1)) tmp) tmp").unwrap_err().kind,
+            ErrorKind::UnboundSymbol(_)
+        ));
+    }
+
+    #[test]
+    fn test_set_mutates_existing_binding() {
+        let src = "(def make-counter (lambda ()
+                     (let ((n 0)) (lambda () (set! n (+ n 1)) n))))
+                   (def c (make-counter))
+                   (c) (c)
+                   (list (c) ((make-counter)))";
+        assert_eq!(run(src).unwrap().to_string(), "(3 1)");
+        assert_eq!(
+            run("(def x 1) ((lambda () (set! x 2))) x").unwrap(),
+            Object::Integer(2)
+        );
+        assert_eq!(
+            run("(set! y 1)").unwrap_err().kind,
+            ErrorKind::UnboundSymbol("y".to_string())
+        );
+    }
+
+    #[test]
+    fn test_control_forms() {
+        assert_eq!(
+            run("(begin (def x 1) (set! x (+ x 1)) x) x").unwrap(),
+            Object::Integer(2)
+        );
+        assert_eq!(run("(when (< 1 2) 1 2)").unwrap(), Object::Integer(2));
+        assert_eq!(run("(unless (< 1 2) 1 2)").unwrap(), Object::Void);
+        let src = "(def sign (lambda (n)
+                     (cond ((< n 0) \"negative\")
+                           ((== n 0) \"zero\")
+                           (else \"positive\"))))
+                   (list (sign -5) (sign 0) (sign 3))";
+        assert_eq!(
+            run(src).unwrap().to_string(),
+            "(\"negative\" \"zero\" \"positive\")"
+        );
+        let src = "(def kind (lambda (n)
+                     (case n ((1 3 5) 'odd) ((2 4) 'even) (else 'big))))
+                   (list (kind 3) (kind 4) (kind 9))";
+        assert_eq!(run(src).unwrap().to_string(), "(odd even big)");
+        let src = "(def loop (lambda (n) (cond ((== n 0) 'done) (else (loop (- n 1))))))
+                   (loop 100000)";
+        assert_eq!(run(src).unwrap().to_string(), "done");
+    }
+
+    #[test]
+    fn test_boolean_logic() {
+        assert_eq!(run("(and 1 2 3)").unwrap(), Object::Integer(3));
+        assert_eq!(run("(and 1 #f x)").unwrap(), Object::Bool(false));
+        assert_eq!(run("(and)").unwrap(), Object::Bool(true));
+        assert_eq!(run("(or #f 2 x)").unwrap(), Object::Integer(2));
+        assert_eq!(run("(or #f #f)").unwrap(), Object::Bool(false));
+        assert_eq!(run("(or)").unwrap(), Object::Bool(false));
+        assert_eq!(run("(not #f)").unwrap(), Object::Bool(true));
+        assert_eq!(run("(not 0)").unwrap(), Object::Bool(false));
+        assert_eq!(run("(if 0 'yes 'no)").unwrap().to_string(), "yes");
+    }
+
+    #[test]
+    fn test_numeric_mixing_and_comparisons() {
+        assert_eq!(run("(+ 1 2.5)").unwrap(), Object::Float(3.5));
+        assert_eq!(run("(* 2 3)").unwrap(), Object::Integer(6));
+        assert_eq!(run("(/ 6 2)").unwrap(), Object::Integer(3));
+        assert_eq!(run("(/ 7 2.0)").unwrap(), Object::Float(3.5));
+        assert_eq!(run("(== 1 1.0)").unwrap(), Object::Bool(true));
+        assert_eq!(run("(>= 3 3 2.5)").unwrap(), Object::Bool(true));
+        assert_eq!(run("(<= 3 2)").unwrap(), Object::Bool(false));
+        assert_eq!(run("(< \"abc\" \"abd\")").unwrap(), Object::Bool(true));
+        assert_eq!(run("(mod -7 2)").unwrap(), Object::Integer(1));
+        assert_eq!(run("(rem -7 2)").unwrap(), Object::Integer(-1));
+        assert_eq!(run("(quotient -7 2)").unwrap(), Object::Integer(-3));
+        assert_eq!(run("(abs -4)").unwrap(), Object::Integer(4));
+        assert_eq!(run("(max 1 3 2)").unwrap(), Object::Integer(3));
+        assert_eq!(run("(min 1 3 0.5)").unwrap(), Object::Float(0.5));
+        assert_eq!(run("(max 1 3 0.5)").unwrap(), Object::Float(3.0));
+        assert_eq!(
+            run("(mod 1 0)").unwrap_err().kind,
+            ErrorKind::DivisionByZero
+        );
+    }
+
+    #[test]
+    fn test_bignums_and_rationals() {
+        assert_eq!(show("(* 9223372036854775807 2)"), "18446744073709551614");
+        assert_eq!(
+            show("(- 18446744073709551614 9223372036854775807)"),
+            "9223372036854775807"
+        );
+        assert_eq!(
+            run("(- 18446744073709551614 18446744073709551613)").unwrap(),
+            Object::Integer(1)
+        );
+        assert_eq!(show("(/ 1 3)"), "1/3");
+        assert_eq!(show("(+ 1/3 2/3)"), "1");
+        assert_eq!(show("(* 2/4 3)"), "3/2");
+        assert_eq!(show("(+ 1/2 0.25)"), "0.75");
+        assert_eq!(run("(< 1/3 0.34)").unwrap(), Object::Bool(true));
+        assert_eq!(
+            show("(def fact (lambda (n) (if (< n 2) 1 (* n (fact (- n 1)))))) (fact 25)"),
+            "15511210043330985984000000"
+        );
+        assert!(matches!(
+            run("(mod 1/2 2)").unwrap_err().kind,
+            ErrorKind::TypeMismatch { .. }
+        ));
+        assert!(matches!(run("1/0").unwrap_err().kind, ErrorKind::Parse(_)));
+    }
+
+    #[test]
+    fn test_math_builtins() {
+        assert_eq!(show("(sqrt 16)"), "4");
+        assert_eq!(show("(sqrt 1/9)"), "1/3");
+        assert_eq!(show("(sqrt 2.25)"), "1.5");
+        assert_eq!(show("(expt 2 100)"), "1267650600228229401496703205376");
+        assert_eq!(show("(expt 2 -2)"), "1/4");
+        assert_eq!(show("(expt 4 0.5)"), "2.0");
+        assert_eq!(show("(exp 0)"), "1.0");
+        assert_eq!(show("(log 100 10)"), "2.0");
+        assert_eq!(run("(== (cos pi) -1)").unwrap(), Object::Bool(true));
+        assert_eq!(run("(< 2.71 e)").unwrap(), Object::Bool(true));
+        assert_eq!(show("(floor -7/2)"), "-4");
+        assert_eq!(show("(ceiling 2.1)"), "3.0");
+        assert_eq!(show("(round 5/2)"), "2");
+        assert_eq!(run("(round 3.5)").unwrap(), Object::Float(4.0));
+        assert_eq!(show("(round 3.5)"), "4.0");
+        assert_eq!(show("(exact->inexact 1)"), "1.0");
+        assert_eq!(show("(truncate -2.7)"), "-2.0");
+        assert_eq!(run("(exact->inexact 1/4)").unwrap(), Object::Float(0.25));
+        assert_eq!(show("(inexact->exact 0.125)"), "1/8");
+    }
+
+    #[test]
+    fn test_hash_maps() {
+        let prelude = "(def h {\"a\" 1 \"b\" (+ 1 1)}) ";
+        let run_with = |src: &str| run(&format!("{}{}", prelude, src));
+        assert_eq!(run_with("(hash-ref h \"b\")").unwrap(), Object::Integer(2));
+        assert_eq!(
+            run_with("(hash-ref h \"c\" 0)").unwrap(),
+            Object::Integer(0)
+        );
+        assert_eq!(
+            run_with("(hash-ref h \"c\")").unwrap_err().kind,
+            ErrorKind::KeyNotFound("\"c\"".to_string())
+        );
+        assert_eq!(
+            run_with("(hash-set! h 3 'x) (hash-remove! h \"a\") (hash-count h)").unwrap(),
+            Object::Integer(2)
+        );
+        assert_eq!(
+            run_with(
+                "(def total 0) (hash-for-each h (lambda (k v) (set! total (+ total v)))) total"
+            )
+            .unwrap(),
+            Object::Integer(3)
+        );
+        assert_eq!(
+            run_with("(== h {\"b\" 2 \"a\" 1})").unwrap(),
+            Object::Bool(true)
+        );
+        assert_eq!(run_with("(== h {\"a\" 1})").unwrap(), Object::Bool(false));
+        assert!(matches!(
+            run("{1.5 1}").unwrap_err().kind,
+            ErrorKind::TypeMismatch { .. }
+        ));
+        assert!(matches!(
+            run("{1 2 3}").unwrap_err().kind,
+            ErrorKind::Parse(_)
+        ));
+        assert_eq!(
+            run("(let ((hash-map 5)) (hash-ref {:a hash-map} :a))").unwrap(),
+            Object::Integer(5)
+        );
+        assert_eq!(
+            run("(hash-ref '{:a (1 2)} :a)").unwrap().to_string(),
+            "(1 2)"
+        );
+        assert_eq!(
+            run("(def x 2) (hash-ref `{:a ,x ,@(list :b 3)} :b)").unwrap(),
+            Object::Integer(3)
+        );
+        assert!(run("`{:a ,@(list 1 :b)}").is_err());
+    }
+
+    #[test]
+    fn test_vectors() {
+        assert_eq!(show("#(1 (+ 1 1) \"three\")"), "#(1 2 \"three\")");
+        assert_eq!(show("(vector-ref #(10 20 30) 1)"), "20");
+        assert_eq!(
+            show("(def v (make-vector 3 0)) (vector-set! v 2 'x) v"),
+            "#(0 0 x)"
+        );
+        assert_eq!(show("(vector-length (list->vector '(1 2)))"), "2");
+        assert_eq!(show("(vector->list #(1 2))"), "(1 2)");
+        assert_eq!(show("(vector-slice #(1 2 3 4) 1 3)"), "#(2 3)");
+        assert_eq!(show("(vector-slice #(1 2 3 4) 2)"), "#(3 4)");
+        assert_eq!(run("(== #(1 2) #(1 2))").unwrap(), Object::Bool(true));
+        assert_eq!(
+            run("(def v (make-vector 1 0)) (vector-set! v 0 v) (== v v)").unwrap(),
+            Object::Bool(true)
+        );
+        assert!(run("(make-vector 100000000000000)").is_err());
+        assert_eq!(show("(let ((vector 5)) #(1 vector))"), "#(1 5)");
+        assert_eq!(run("(vector? '#(1 2))").unwrap(), Object::Bool(true));
+        assert_eq!(show("'(a #(b c))"), "(a #(b c))");
+        assert_eq!(show("(def x 2) `#(1 ,x ,@(list 3 4))"), "#(1 2 3 4)");
+        assert_eq!(
+            run("(def x 1) (vector? `#(1 ,x))").unwrap(),
+            Object::Bool(true)
+        );
+        assert_eq!(
+            run("(vector-ref #(1 2) 2)").unwrap_err().kind,
+            ErrorKind::IndexOutOfBounds { index: 2, len: 2 }
+        );
+    }
+
+    #[test]
+    fn test_symbols_and_keywords() {
+        assert_eq!(run("(eq? 'a 'a)").unwrap(), Object::Bool(true));
+        assert_eq!(run("(eq? 'a 'b)").unwrap(), Object::Bool(false));
+        assert_eq!(
+            run("(eq? (string->symbol \"abc\") 'abc)").unwrap(),
+            Object::Bool(true)
+        );
+        assert_eq!(show("(symbol->string 'abc)"), "\"abc\"");
+        assert_eq!(run("(symbol? 'a)").unwrap(), Object::Bool(true));
+        assert_eq!(run("(symbol? 'if)").unwrap(), Object::Bool(true));
+        assert_eq!(run("(symbol? '+)").unwrap(), Object::Bool(true));
+        assert_eq!(run("(symbol? 'lambda)").unwrap(), Object::Bool(true));
+        assert_eq!(show("(symbol->string 'if)"), "\"if\"");
+        assert_eq!(show("(symbol->string '+)"), "\"+\"");
+        assert_eq!(
+            run("(eq? (string->symbol \"if\") 'if)").unwrap(),
+            Object::Bool(true)
+        );
+        assert_eq!(
+            run("(eq? (string->symbol \"+\") '+)").unwrap(),
+            Object::Bool(true)
+        );
+        assert_eq!(show("'(if a (+ b 1))"), "(if a (+ b 1))");
+        assert_eq!(show("(hash-ref {'if 1} (string->symbol \"if\"))"), "1");
+        assert_eq!(show(":key"), ":key");
+        assert_eq!(run("(eq? :a :a)").unwrap(), Object::Bool(true));
+        assert_eq!(run("(keyword? :a)").unwrap(), Object::Bool(true));
+        assert_eq!(show("(keyword->string :a)"), "\"a\"");
+        assert_eq!(show("(hash-ref {:a 1 'b 2} :a)"), "1");
+        assert_eq!(show("(hash-ref {:a 1 'b 2} 'b)"), "2");
+        assert_eq!(run("(def v #(1)) (eq? v v)").unwrap(), Object::Bool(true));
+        assert_eq!(run("(eq? #(1) #(1))").unwrap(), Object::Bool(false));
+    }
+
+    #[test]
+    fn test_chars() {
+        assert_eq!(run("#\\a").unwrap(), Object::Char('a'));
+        assert_eq!(
+            show("(list #\\space #\\newline #\\( #\\x)"),
+            "(#\\space #\\newline #\\( #\\x)"
+        );
+        assert_eq!(show("(char->integer #\\A)"), "65");
+        assert_eq!(show("(integer->char 955)"), "#\\λ");
+        assert_eq!(run("#\\λ").unwrap(), Object::Char('λ'));
+        assert_eq!(show("(list #\\é 1)"), "(#\\é 1)");
+        assert_eq!(show("(char-upcase #\\a)"), "#\\A");
+        assert_eq!(run("(char-alphabetic? #\\1)").unwrap(), Object::Bool(false));
+        assert_eq!(run("(char-numeric? #\\1)").unwrap(), Object::Bool(true));
+        assert_eq!(run("(< #\\a #\\b)").unwrap(), Object::Bool(true));
+        assert_eq!(show("(string->list \"hi\")"), "(#\\h #\\i)");
+        assert_eq!(show("(list->string (list #\\o #\\k))"), "\"ok\"");
+        assert!(matches!(
+            run("#\\bogus").unwrap_err().kind,
+            ErrorKind::Parse(_)
+        ));
+    }
+
+    #[test]
+    fn test_nil_and_truthiness() {
+        assert_eq!(show("nil"), "()");
+        assert_eq!(show("()"), "()");
+        assert_eq!(run("(== nil '())").unwrap(), Object::Bool(true));
+        assert_eq!(run("(null? nil)").unwrap(), Object::Bool(true));
+        assert_eq!(run("(null? #f)").unwrap(), Object::Bool(false));
+        assert_eq!(show("(cons 1 nil)"), "(1)");
+        assert_eq!(show("(if nil 'yes 'no)"), "no");
+        assert_eq!(show("(if (when #f 1) 'yes 'no)"), "no");
+        assert_eq!(show("(if 0 'yes 'no)"), "yes");
+        assert_eq!(show("(if \"\" 'yes 'no)"), "yes");
+        assert_eq!(show("(if #() 'yes 'no)"), "yes");
+        assert_eq!(show("(or nil 2)"), "2");
+        assert_eq!(run("(void? (when #f 1))").unwrap(), Object::Bool(true));
+        assert_eq!(run("(void? nil)").unwrap(), Object::Bool(false));
+        assert_eq!(show("(list (when #f 1) nil)"), "(Void ())");
+    }
+
+    #[test]
+    fn test_error_span_points_at_innermost_form() {
+        let err = run("(+ 1 (* 2 x))").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnboundSymbol("x".to_string()));
+        let span = err.span.unwrap();
+        assert_eq!((span.start, span.end), (5, 12));
+
+        // Lists built by a macro have no span of their own, so the error
+        // points at the macro call.
+        let err = run("(defmacro m (x) (list (quote car) x))\n(m 1)").unwrap_err();
+        let span = err.span.unwrap();
+        assert_eq!((span.start, span.end), (38, 43));
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        assert!(matches!(
+            run("(+ 1 #t)").unwrap_err().kind,
+            ErrorKind::TypeMismatch { .. }
+        ));
+        assert!(matches!(
+            run("(def x)").unwrap_err().kind,
+            ErrorKind::Arity { .. }
+        ));
+        assert_eq!(run("(/ 1 0)").unwrap_err().kind, ErrorKind::DivisionByZero);
+        assert!(matches!(
+            run("(substring \"abc\" 100000000000000000000)")
+                .unwrap_err()
+                .kind,
+            ErrorKind::Overflow(_)
+        ));
+        assert!(matches!(
+            run("(+ 1 2").unwrap_err().kind,
+            ErrorKind::Parse(_)
+        ));
     }
 }
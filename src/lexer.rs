@@ -1,5 +1,7 @@
 use logos::Logos;
 
+use crate::object::Span;
+
 #[derive(Logos, Debug, Clone, Copy, PartialEq)]
 pub enum LexerToken<'a> {
     #[regex(r"[ \t\n\f]+", logos::skip)]
@@ -9,12 +11,32 @@ pub enum LexerToken<'a> {
     #[regex("-?[0-9]+", |lex| lex.slice().parse())]
     Integer(i64),
 
+    // Literals of 19 digits or more may not fit in an `Integer`, so they are
+    // left for the parser to read as bignums. Logos has no `{n}` repetition.
+    #[regex(
+        "-?[0-9][0-9][0-9][0-9][0-9][0-9][0-9][0-9][0-9][0-9][0-9][0-9][0-9][0-9][0-9][0-9][0-9][0-9][0-9]+",
+        priority = 10,
+        callback = |lex| lex.slice()
+    )]
+    BigInteger(&'a str),
+
+    #[regex("-?[0-9]+/[0-9]+", |lex| lex.slice())]
+    Rational(&'a str),
+
     #[regex(r"-?[0-9]+\.[0-9]*", |lex| lex.slice().parse())]
     Float(f64),
 
-    #[regex("#t|#f", |lex| if lex.slice() == "#t" {true} else {false})]
+    #[regex("#t|#f", |lex| lex.slice() == "#t")]
     Bool(bool),
 
+    // `#\a` or a named character like `#\space`; the parser resolves names.
+    #[regex(r"#\\[a-zA-Z]*", lex_char)]
+    Char(&'a str),
+
+    // Escapes are kept as written and resolved by the parser.
+    #[regex(r#""([^"\\]|\\.)*""#, |lex| { let s = lex.slice(); &s[1..s.len() - 1] })]
+    String(&'a str),
+
     #[token("+")]
     OpAdd,
 
@@ -30,9 +52,15 @@ pub enum LexerToken<'a> {
     #[token(">")]
     OpGreater,
 
+    #[token(">=")]
+    OpGreaterEq,
+
     #[token("<")]
     OpSmaller,
 
+    #[token("<=")]
+    OpSmallerEq,
+
     #[token("==")]
     OpEqual,
 
@@ -45,19 +73,83 @@ pub enum LexerToken<'a> {
     #[token(")")]
     ParenthesesClose,
 
-    #[regex("def|lambda", |lex| lex.slice())]
-    Keyword(&'a str),
+    #[token("#(")]
+    VectorOpen,
+
+    #[token(".")]
+    Dot,
+
+    #[token("{")]
+    BraceOpen,
+
+    #[token("}")]
+    BraceClose,
+
+    #[token("'")]
+    Quote,
+
+    #[token("`")]
+    Quasiquote,
+
+    #[token(",")]
+    Unquote,
+
+    #[token(",@")]
+    UnquoteSplicing,
+
+    #[regex(
+        "def|lambda|quote|quasiquote|unquote|unquote-splicing|defmacro|macroexpand|macroexpand-1|define-syntax|syntax-rules|let|let\\*|letrec|set!|begin|when|unless|cond|case|and|or",
+        |lex| lex.slice()
+    )]
+    SpecialForm(&'a str),
 
     #[token("if")]
     If,
 
-    #[regex(r"[a-zA-Z][a-zA-Z0-9_-]*", priority = 2, callback = |lex| lex.slice())]
+    #[token("nil")]
+    Nil,
+
+    // `:name` keywords evaluate to themselves.
+    #[regex(r":[a-zA-Z_][a-zA-Z0-9_?!*<>=/+-]*", |lex| &lex.slice()[1..])]
+    Keyword(&'a str),
+
+    #[regex(r"&?[a-zA-Z_][a-zA-Z0-9_?!*<>=/+-]*", priority = 2, callback = |lex| lex.slice())]
+    #[token("...", |lex| lex.slice())]
     Name(&'a str),
 }
 
-pub fn lexing<'a>(str: &'a str) -> Vec<LexerToken<'a>> {
-    let lex = LexerToken::lexer(&str);
-    lex.collect()
+/// Regexes match bytes, so a character outside the ASCII names is taken whole
+/// from the remainder of the input.
+fn lex_char<'a>(lex: &mut logos::Lexer<'a, LexerToken<'a>>) -> Option<&'a str> {
+    if lex.slice().len() == 2 {
+        let c = lex.remainder().chars().next()?;
+        lex.bump(c.len_utf8());
+    }
+    Some(&lex.slice()[2..])
+}
+
+pub fn lexing<'a>(str: &'a str) -> Vec<(LexerToken<'a>, Span)> {
+    let lex = LexerToken::lexer(str);
+    lex.spanned().map(|(t, s)| (t, Span::from(s))).collect()
+}
+
+/// Number of brackets `str` leaves open, or the span of the first closing
+/// bracket that has no opening one. Brackets inside string and character
+/// literals are not counted.
+pub fn open_brackets(str: &str) -> Result<usize, Span> {
+    let mut depth = 0usize;
+    for (token, span) in lexing(str) {
+        match token {
+            LexerToken::ParenthesesOpen | LexerToken::VectorOpen | LexerToken::BraceOpen => {
+                depth += 1
+            }
+            LexerToken::ParenthesesClose | LexerToken::BraceClose => {
+                depth = depth.checked_sub(1).ok_or(span)?
+            }
+            _ => {}
+        }
+    }
+    Ok(depth)
 }
 
 #[cfg(test)]
@@ -137,7 +229,7 @@ mod lexer_tests {
         assert_eq!(lex.next(), Some(LexerToken::ParenthesesOpen));
         assert_eq!(lex.slice(), "(");
 
-        assert_eq!(lex.next(), Some(LexerToken::Keyword("def")));
+        assert_eq!(lex.next(), Some(LexerToken::SpecialForm("def")));
         assert_eq!(lex.slice(), "def");
 
         assert_eq!(lex.next(), Some(LexerToken::Name("main")));
@@ -146,7 +238,7 @@ mod lexer_tests {
         assert_eq!(lex.next(), Some(LexerToken::ParenthesesOpen));
         assert_eq!(lex.slice(), "(");
 
-        assert_eq!(lex.next(), Some(LexerToken::Keyword("lambda")));
+        assert_eq!(lex.next(), Some(LexerToken::SpecialForm("lambda")));
         assert_eq!(lex.slice(), "lambda");
 
         assert_eq!(lex.next(), Some(LexerToken::ParenthesesOpen));
@@ -200,7 +292,7 @@ mod lexer_tests {
         assert_eq!(lex.next(), Some(LexerToken::ParenthesesOpen));
         assert_eq!(lex.slice(), "(");
 
-        assert_eq!(lex.next(), Some(LexerToken::Keyword("print")));
+        assert_eq!(lex.next(), Some(LexerToken::Name("print")));
         assert_eq!(lex.slice(), "print");
 
         assert_eq!(lex.next(), Some(LexerToken::Integer(2)));
@@ -215,6 +307,126 @@ mod lexer_tests {
         assert_eq!(lex.next(), None);
     }
 
+    #[test]
+    fn test_string() {
+        let mut lex = LexerToken::lexer(r#"(string-append "a \"b\"" "")"#);
+
+        assert_eq!(lex.next(), Some(LexerToken::ParenthesesOpen));
+        assert_eq!(lex.slice(), "(");
+
+        assert_eq!(lex.next(), Some(LexerToken::Name("string-append")));
+        assert_eq!(lex.slice(), "string-append");
+
+        assert_eq!(lex.next(), Some(LexerToken::String(r#"a \"b\""#)));
+        assert_eq!(lex.slice(), r#""a \"b\"""#);
+
+        assert_eq!(lex.next(), Some(LexerToken::String("")));
+        assert_eq!(lex.slice(), r#""""#);
+
+        assert_eq!(lex.next(), Some(LexerToken::ParenthesesClose));
+        assert_eq!(lex.slice(), ")");
+
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_quote_reader_macros() {
+        let mut lex = LexerToken::lexer("`(a ,b ,@'c)");
+
+        assert_eq!(lex.next(), Some(LexerToken::Quasiquote));
+        assert_eq!(lex.slice(), "`");
+
+        assert_eq!(lex.next(), Some(LexerToken::ParenthesesOpen));
+        assert_eq!(lex.slice(), "(");
+
+        assert_eq!(lex.next(), Some(LexerToken::Name("a")));
+        assert_eq!(lex.slice(), "a");
+
+        assert_eq!(lex.next(), Some(LexerToken::Unquote));
+        assert_eq!(lex.slice(), ",");
+
+        assert_eq!(lex.next(), Some(LexerToken::Name("b")));
+        assert_eq!(lex.slice(), "b");
+
+        assert_eq!(lex.next(), Some(LexerToken::UnquoteSplicing));
+        assert_eq!(lex.slice(), ",@");
+
+        assert_eq!(lex.next(), Some(LexerToken::Quote));
+        assert_eq!(lex.slice(), "'");
+
+        assert_eq!(lex.next(), Some(LexerToken::Name("c")));
+        assert_eq!(lex.slice(), "c");
+
+        assert_eq!(lex.next(), Some(LexerToken::ParenthesesClose));
+        assert_eq!(lex.slice(), ")");
+
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_big_and_rational_numbers() {
+        let mut lex = LexerToken::lexer("9223372036854775807 123456789012345678901 -1/3");
+
+        assert_eq!(
+            lex.next(),
+            Some(LexerToken::BigInteger("9223372036854775807"))
+        );
+        assert_eq!(
+            lex.next(),
+            Some(LexerToken::BigInteger("123456789012345678901"))
+        );
+        assert_eq!(lex.next(), Some(LexerToken::Rational("-1/3")));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_colon_keywords() {
+        let mut lex = LexerToken::lexer("(:a :is-b? c)");
+
+        assert_eq!(lex.next(), Some(LexerToken::ParenthesesOpen));
+        assert_eq!(lex.next(), Some(LexerToken::Keyword("a")));
+        assert_eq!(lex.next(), Some(LexerToken::Keyword("is-b?")));
+        assert_eq!(lex.next(), Some(LexerToken::Name("c")));
+        assert_eq!(lex.next(), Some(LexerToken::ParenthesesClose));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_let_keywords() {
+        let mut lex = LexerToken::lexer("(let* letrec let lettuce)");
+
+        assert_eq!(lex.next(), Some(LexerToken::ParenthesesOpen));
+        assert_eq!(lex.slice(), "(");
+
+        assert_eq!(lex.next(), Some(LexerToken::SpecialForm("let*")));
+        assert_eq!(lex.slice(), "let*");
+
+        assert_eq!(lex.next(), Some(LexerToken::SpecialForm("letrec")));
+        assert_eq!(lex.slice(), "letrec");
+
+        assert_eq!(lex.next(), Some(LexerToken::SpecialForm("let")));
+        assert_eq!(lex.slice(), "let");
+
+        assert_eq!(lex.next(), Some(LexerToken::Name("lettuce")));
+        assert_eq!(lex.slice(), "lettuce");
+
+        assert_eq!(lex.next(), Some(LexerToken::ParenthesesClose));
+        assert_eq!(lex.slice(), ")");
+
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_open_brackets() {
+        assert_eq!(open_brackets("(def a (+ 1"), Ok(2));
+        assert_eq!(open_brackets("#(1 {:a 2})"), Ok(0));
+        assert_eq!(open_brackets("(def s \"a)b\")"), Ok(0));
+        assert_eq!(open_brackets("(def s \"a(b\")"), Ok(0));
+        assert!(open_brackets("(+ 1 2))").is_err());
+        assert_eq!(open_brackets("(list #\\()"), Ok(0));
+        assert_eq!(open_brackets("#\\)"), Ok(0));
+    }
+
     // #[test]
     // fn test_all() {
     //     let mut lex = LexerToken::lexer(
@@ -230,7 +442,7 @@ mod lexer_tests {
     //     assert_eq!(lex.next(), Some(LexerToken::ParenthesesOpen));
     //     assert_eq!(lex.slice(), "(");
 
-    //     assert_eq!(lex.next(), Some(LexerToken::Keyword("def")));
+    //     assert_eq!(lex.next(), Some(LexerToken::SpecialForm("def")));
     //     assert_eq!(lex.slice(), "def");
 
     //     assert_eq!(lex.next(), Some(LexerToken::Name("circle-area")));
@@ -239,7 +451,7 @@ mod lexer_tests {
     //     assert_eq!(lex.next(), Some(LexerToken::ParenthesesOpen));
     //     assert_eq!(lex.slice(), "(");
 
-    //     assert_eq!(lex.next(), Some(LexerToken::Keyword("lambda")));
+    //     assert_eq!(lex.next(), Some(LexerToken::SpecialForm("lambda")));
     //     assert_eq!(lex.slice(), "lambda");
 
     //     assert_eq!(lex.next(), Some(LexerToken::ParenthesesOpen));
@@ -251,7 +463,7 @@ mod lexer_tests {
     //     assert_eq!(lex.next(), Some(LexerToken::ParenthesesOpen));
     //     assert_eq!(lex.slice(), "(");
 
-    //     assert_eq!(lex.next(), Some(LexerToken::Keyword("def")));
+    //     assert_eq!(lex.next(), Some(LexerToken::SpecialForm("def")));
     //     assert_eq!(lex.slice(), "def");
 
     //     assert_eq!(lex.next(), Some(LexerToken::Name("pi")));
@@ -266,7 +478,7 @@ mod lexer_tests {
     //     assert_eq!(lex.next(), Some(LexerToken::ParenthesesOpen));
     //     assert_eq!(lex.slice(), "(");
 
-    //     assert_eq!(lex.next(), Some(LexerToken::Keyword("def")));
+    //     assert_eq!(lex.next(), Some(LexerToken::SpecialForm("def")));
     //     assert_eq!(lex.slice(), "def");
 
     //     assert_eq!(lex.next(), Some(LexerToken::Name("r")));
@@ -281,7 +493,7 @@ mod lexer_tests {
     //     assert_eq!(lex.next(), Some(LexerToken::ParenthesesOpen));
     //     assert_eq!(lex.slice(), "(");
 
-    //     assert_eq!(lex.next(), Some(LexerToken::Keyword("def")));
+    //     assert_eq!(lex.next(), Some(LexerToken::SpecialForm("def")));
     //     assert_eq!(lex.slice(), "def");
 
     //     assert_eq!(lex.next(), Some(LexerToken::Name("sqr")));
@@ -0,0 +1,581 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::error::LispError;
+use crate::eval::apply_named;
+use crate::object::{Alias, Object, Span, SyntaxRules};
+use crate::scope::Scope;
+
+thread_local! {
+    static NEXT_ALIAS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Looks an identifier up in `scope`. Aliases introduced by a `syntax-rules`
+/// template that are not bound by the expansion itself fall back to the scope
+/// the macro was defined in.
+pub fn lookup(ident: &Object, scope: &Rc<RefCell<Scope>>) -> Option<Object> {
+    match ident {
+        Object::Name(name) => scope.borrow().get(name),
+        Object::Alias(alias) => {
+            let obj = scope.borrow().get(&alias.name);
+            obj.or_else(|| lookup(&alias.original, &alias.env))
+        }
+        _ => None,
+    }
+}
+
+/// Updates the binding `lookup` would find for `ident`, handing the value
+/// back if there is none.
+pub fn assign(ident: &Object, val: Object, scope: &Rc<RefCell<Scope>>) -> Result<(), Object> {
+    let name = match ident.name() {
+        Some(name) => name,
+        None => return Err(val),
+    };
+    let val = match scope.borrow_mut().assign(name, val) {
+        Ok(()) => return Ok(()),
+        Err(val) => val,
+    };
+    match ident {
+        Object::Alias(alias) => assign(&alias.original, val, &alias.env),
+        _ => Err(val),
+    }
+}
+
+/// Name an identifier was written as, before any renaming by `syntax-rules`.
+pub fn base_name(ident: &Object) -> Option<&str> {
+    match ident {
+        Object::Name(name) => Some(name),
+        Object::Alias(alias) => base_name(&alias.original),
+        _ => None,
+    }
+}
+
+/// Applies a macro or `syntax-rules` transformer to the call `form`.
+pub fn expand(transformer: &Object, form: &[Object]) -> Result<Object, LispError> {
+    match transformer {
+        Object::Macro(lambda) => Ok(to_code(&apply_named(
+            &Object::Lambda(lambda.clone()),
+            base_name(&form[0]).unwrap_or("macro"),
+            &form[1..],
+        )?)),
+        Object::Syntax(rules) => expand_syntax_rules(rules, form),
+        o => Err(LispError::type_mismatch("Macro", o.type_name())),
+    }
+}
+
+/// Turns pairs built by `cons` in a macro expansion back into the plain lists
+/// code is made of. Improper lists are left as they are.
+fn to_code(obj: &Object) -> Object {
+    let span = match obj {
+        Object::List(_, span) => *span,
+        Object::Pair(_) => Span::default(),
+        o => return o.clone(),
+    };
+    match obj.list_items() {
+        Some(items) => Object::List(items.iter().map(to_code).collect(), span),
+        None => obj.clone(),
+    }
+}
+
+/// Expands `form` once if it is a call to a macro, returning `None` otherwise.
+pub fn macroexpand_1(
+    form: &Object,
+    scope: &Rc<RefCell<Scope>>,
+) -> Result<Option<Object>, LispError> {
+    let list = match form {
+        Object::List(l, _) => l,
+        _ => return Ok(None),
+    };
+    let head = match list.first() {
+        Some(head @ (Object::Name(_) | Object::Alias(_))) => head,
+        _ => return Ok(None),
+    };
+
+    match lookup(head, scope) {
+        Some(transformer @ (Object::Macro(_) | Object::Syntax(_))) => {
+            let expansion = expand(&transformer, list).map_err(|e| match form {
+                Object::List(_, span) => e.with_span(*span),
+                _ => e,
+            })?;
+            Ok(Some(expansion))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Expands `form` until its head is no longer a macro. Subforms are left as
+/// they are.
+pub fn macroexpand(form: &Object, scope: &Rc<RefCell<Scope>>) -> Result<Object, LispError> {
+    let mut form = form.clone();
+    while let Some(expansion) = macroexpand_1(&form, scope)? {
+        form = expansion;
+    }
+    Ok(form)
+}
+
+/// Expands every macro call in `form`, including nested ones. Quoted data and
+/// parameter lists are not touched, and neither are calls whose head is a
+/// name bound by an enclosing `lambda` or `let`, since at run time that head
+/// is the local value rather than the macro.
+pub fn macroexpand_all(form: &Object, scope: &Rc<RefCell<Scope>>) -> Result<Object, LispError> {
+    expand_all(form, scope, &mut Vec::new())
+}
+
+/// `macroexpand_all` with the names bound locally around `form`.
+fn expand_all(
+    form: &Object,
+    scope: &Rc<RefCell<Scope>>,
+    locals: &mut Vec<String>,
+) -> Result<Object, LispError> {
+    let mut form = form.clone();
+    while !calls_local(&form, locals) {
+        match macroexpand_1(&form, scope)? {
+            Some(expansion) => form = expansion,
+            None => break,
+        }
+    }
+    let (list, span) = match &form {
+        Object::List(l, span) => (l, *span),
+        _ => return Ok(form),
+    };
+
+    if let Some(Object::SpecialForm(k)) = list.first() {
+        match k.as_str() {
+            "let" | "let*" | "letrec" if list.len() >= 2 => {
+                return expand_let(k, list, span, scope, locals)
+            }
+            "cond" => return expand_clauses(list, 1, 0, span, scope, locals),
+            "case" => return expand_clauses(list, 2, 1, span, scope, locals),
+            _ => {}
+        }
+    }
+
+    let (verbatim, params) = match list.first() {
+        Some(Object::SpecialForm(k)) => match k.as_str() {
+            "quote" | "quasiquote" | "syntax-rules" | "define-syntax" => (list.len(), None),
+            "lambda" => (2, list.get(1)),
+            "defmacro" => (3, list.get(2)),
+            _ => (1, None),
+        },
+        _ => (0, None),
+    };
+
+    let depth = locals.len();
+    if let Some(params) = params {
+        locals.extend(param_names(params));
+    }
+    let mut expanded = list[..verbatim.min(list.len())].to_vec();
+    for o in list.iter().skip(verbatim) {
+        expanded.push(expand_all(o, scope, locals)?);
+    }
+    locals.truncate(depth);
+    Ok(Object::List(expanded.into(), span))
+}
+
+/// Whether `form` is a call whose head is a locally bound name.
+fn calls_local(form: &Object, locals: &[String]) -> bool {
+    match form {
+        Object::List(l, _) => l
+            .first()
+            .and_then(Object::name)
+            .is_some_and(|name| locals.iter().any(|local| local == name)),
+        _ => false,
+    }
+}
+
+/// Names bound by a parameter list such as `(a &optional (b 1) &rest c)` or
+/// `(a . rest)`.
+fn param_names(params: &Object) -> Vec<String> {
+    let (list, rest) = match params {
+        Object::List(l, _) => (l.to_vec(), None),
+        Object::Pair(_) => {
+            let (list, rest) = params.split_tail();
+            (list, Some(rest))
+        }
+        _ => return Vec::new(),
+    };
+    list.iter()
+        .chain(&rest)
+        .filter_map(|o| match o {
+            Object::List(l, _) => l.first().and_then(Object::name),
+            o => o.name(),
+        })
+        .filter(|name| !name.starts_with('&'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Expands the init forms and the body of a `let` form, leaving the bound
+/// names alone. Each init sees the names its form makes visible to it.
+fn expand_let(
+    form: &str,
+    list: &[Object],
+    span: Span,
+    scope: &Rc<RefCell<Scope>>,
+    locals: &mut Vec<String>,
+) -> Result<Object, LispError> {
+    let depth = locals.len();
+    let bindings = match &list[1] {
+        Object::List(bindings, bindings_span) => {
+            let names: Vec<String> = bindings
+                .iter()
+                .filter_map(|b| match b {
+                    Object::List(l, _) => l.first().and_then(Object::name).map(str::to_string),
+                    _ => None,
+                })
+                .collect();
+            if form == "letrec" {
+                locals.extend(names.iter().cloned());
+            }
+            let mut expanded = Vec::new();
+            for binding in bindings {
+                expanded.push(match binding {
+                    Object::List(l, s) if l.len() == 2 => {
+                        let init = expand_all(&l[1], scope, locals)?;
+                        if form == "let*" {
+                            locals.extend(l[0].name().map(str::to_string));
+                        }
+                        Object::List(vec![l[0].clone(), init].into(), *s)
+                    }
+                    o => o.clone(),
+                });
+            }
+            locals.truncate(depth);
+            locals.extend(names);
+            Object::List(expanded.into(), *bindings_span)
+        }
+        o => o.clone(),
+    };
+
+    let mut expanded = vec![list[0].clone(), bindings];
+    for o in &list[2..] {
+        expanded.push(expand_all(o, scope, locals)?);
+    }
+    locals.truncate(depth);
+    Ok(Object::List(expanded.into(), span))
+}
+
+/// Expands the clauses of `cond` and `case` element by element, so a clause is
+/// never mistaken for a macro call. The first `verbatim` elements of each
+/// clause are kept as they are.
+fn expand_clauses(
+    list: &[Object],
+    first_clause: usize,
+    verbatim: usize,
+    span: Span,
+    scope: &Rc<RefCell<Scope>>,
+    locals: &mut Vec<String>,
+) -> Result<Object, LispError> {
+    let mut expanded = Vec::new();
+    for (i, o) in list.iter().enumerate() {
+        if i == 0 {
+            expanded.push(o.clone());
+            continue;
+        }
+        if i < first_clause {
+            expanded.push(expand_all(o, scope, locals)?);
+            continue;
+        }
+        match o {
+            Object::List(clause, clause_span) => {
+                let mut res = clause[..verbatim.min(clause.len())].to_vec();
+                for c in clause.iter().skip(verbatim) {
+                    res.push(expand_all(c, scope, locals)?);
+                }
+                expanded.push(Object::List(res.into(), *clause_span));
+            }
+            o => expanded.push(o.clone()),
+        }
+    }
+    Ok(Object::List(expanded.into(), span))
+}
+
+/// Reads `((literal ...) (pattern template) ...)`, the arguments of a
+/// `syntax-rules` form.
+pub fn parse_syntax_rules(
+    args: &[Object],
+    scope: &Rc<RefCell<Scope>>,
+) -> Result<SyntaxRules, LispError> {
+    let (literals, rules) = match args.split_first() {
+        Some((Object::List(literals, _), rules)) => (literals, rules),
+        Some((o, _)) => return Err(LispError::type_mismatch("List", o.type_name())),
+        None => return Err(LispError::arity("syntax-rules", "at least 1", 0)),
+    };
+
+    let mut names = Vec::new();
+    for o in literals {
+        match o.name() {
+            Some(s) => names.push(s.to_string()),
+            None => return Err(LispError::type_mismatch("Name", o.type_name())),
+        }
+    }
+
+    let mut parsed = Vec::new();
+    for rule in rules {
+        match rule {
+            Object::List(l, _) if l.len() == 2 => match &l[0] {
+                Object::List(p, _) if !p.is_empty() => {
+                    check_pattern(&l[0], 1)?;
+                    parsed.push((l[0].clone(), l[1].clone()));
+                }
+                _ => {
+                    return Err(LispError::invalid_form(
+                        "syntax-rules pattern must be a non-empty list",
+                    ))
+                }
+            },
+            _ => {
+                return Err(LispError::invalid_form(
+                    "syntax-rules rule must be (pattern template)",
+                ))
+            }
+        }
+    }
+
+    Ok(SyntaxRules {
+        literals: names,
+        rules: parsed,
+        env: scope.clone(),
+    })
+}
+
+fn is_ellipsis(obj: &Object) -> bool {
+    matches!(obj, Object::Name(s) if s == "...")
+}
+
+/// Checks that every ellipsis in a pattern follows a subpattern and appears
+/// at most once per list. The head of the top-level pattern stands for the
+/// macro keyword, so an ellipsis there must come after the first argument.
+fn check_pattern(pattern: &Object, first: usize) -> Result<(), LispError> {
+    if let Object::List(l, _) = pattern {
+        let ellipses: Vec<usize> = l
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| is_ellipsis(o))
+            .map(|(i, _)| i)
+            .collect();
+        if ellipses.len() > 1 || ellipses.first().is_some_and(|i| *i <= first) {
+            return Err(LispError::invalid_form(format!(
+                "Misplaced ellipsis in pattern {}",
+                pattern
+            )));
+        }
+        for o in l {
+            check_pattern(o, 0)?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone)]
+enum Binding {
+    One(Object),
+    Many(Vec<Binding>),
+}
+
+type Bindings = HashMap<String, Binding>;
+
+fn expand_syntax_rules(rules: &SyntaxRules, form: &[Object]) -> Result<Object, LispError> {
+    for (pattern, template) in &rules.rules {
+        let pattern = match pattern {
+            Object::List(p, _) => p,
+            _ => continue,
+        };
+        let mut bindings = Bindings::new();
+        if match_list(&pattern[1..], &form[1..], rules, &mut bindings) {
+            let mut renames = HashMap::new();
+            return instantiate(template, &bindings, rules, &mut renames, true);
+        }
+    }
+
+    let call = Object::List(form.to_vec().into(), Default::default());
+    Err(LispError::invalid_form(format!(
+        "No syntax-rules pattern matches {}",
+        call
+    )))
+}
+
+fn is_literal(name: &str, rules: &SyntaxRules) -> bool {
+    rules.literals.iter().any(|l| l == name)
+}
+
+fn match_pattern(pattern: &Object, form: &Object, rules: &SyntaxRules, b: &mut Bindings) -> bool {
+    match (pattern, pattern.name()) {
+        (_, Some("_")) => true,
+        (_, Some(p)) if is_literal(p, rules) => base_name(form) == base_name(pattern),
+        (_, Some(p)) => {
+            b.insert(p.to_string(), Binding::One(form.clone()));
+            true
+        }
+        (Object::List(ps, _), None) => match form {
+            Object::List(fs, _) => match_list(ps, fs, rules, b),
+            _ => false,
+        },
+        (p, None) => p == form,
+    }
+}
+
+fn match_list(ps: &[Object], fs: &[Object], rules: &SyntaxRules, b: &mut Bindings) -> bool {
+    let ellipsis = match ps.iter().position(is_ellipsis) {
+        Some(i) => i,
+        None => {
+            return ps.len() == fs.len()
+                && ps
+                    .iter()
+                    .zip(fs)
+                    .all(|(p, f)| match_pattern(p, f, rules, b))
+        }
+    };
+
+    let before = &ps[..ellipsis - 1];
+    let repeated = &ps[ellipsis - 1];
+    let after = &ps[ellipsis + 1..];
+    if fs.len() < before.len() + after.len() {
+        return false;
+    }
+    let count = fs.len() - before.len() - after.len();
+    let (fs_before, rest) = fs.split_at(before.len());
+    let (fs_repeated, fs_after) = rest.split_at(count);
+
+    if !match_list(before, fs_before, rules, b) {
+        return false;
+    }
+
+    let mut matches = Vec::new();
+    for f in fs_repeated {
+        let mut inner = Bindings::new();
+        if !match_pattern(repeated, f, rules, &mut inner) {
+            return false;
+        }
+        matches.push(inner);
+    }
+    let mut vars = Vec::new();
+    pattern_vars(repeated, rules, &mut vars);
+    for var in vars {
+        let seq = matches.iter_mut().filter_map(|m| m.remove(&var)).collect();
+        b.insert(var, Binding::Many(seq));
+    }
+
+    match_list(after, fs_after, rules, b)
+}
+
+fn pattern_vars(pattern: &Object, rules: &SyntaxRules, vars: &mut Vec<String>) {
+    match (pattern, pattern.name()) {
+        (_, Some(p)) if p != "_" && p != "..." && !is_literal(p, rules) => vars.push(p.to_string()),
+        (Object::List(l, _), _) => {
+            for o in l {
+                pattern_vars(o, rules, vars);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Pattern variables in `template` that are bound to a sequence.
+fn sequence_vars(template: &Object, b: &Bindings, vars: &mut Vec<String>) {
+    match (template, template.name()) {
+        (_, Some(n))
+            if matches!(b.get(n), Some(Binding::Many(_))) && !vars.iter().any(|v| v == n) =>
+        {
+            vars.push(n.to_string())
+        }
+        (Object::List(l, _), _) => {
+            for o in l {
+                sequence_vars(o, b, vars);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn instantiate(
+    template: &Object,
+    b: &Bindings,
+    rules: &SyntaxRules,
+    renames: &mut HashMap<String, Object>,
+    rename: bool,
+) -> Result<Object, LispError> {
+    if let Some(n) = template.name() {
+        return match b.get(n) {
+            Some(Binding::One(o)) => Ok(o.clone()),
+            Some(Binding::Many(_)) => Err(LispError::invalid_form(format!(
+                "Pattern variable {} must be followed by an ellipsis",
+                n
+            ))),
+            None if rename && !n.starts_with('&') => Ok(alias(template, rules, renames)),
+            None => Ok(template.clone()),
+        };
+    }
+    match template {
+        Object::List(ts, span) => {
+            // Quoted parts of a template are data and keep their names.
+            let rename =
+                rename && !matches!(ts.first(), Some(Object::SpecialForm(k)) if k == "quote");
+            let mut res = Vec::new();
+            let mut i = 0;
+            while i < ts.len() {
+                if !ts.get(i + 1).is_some_and(is_ellipsis) {
+                    res.push(instantiate(&ts[i], b, rules, renames, rename)?);
+                    i += 1;
+                    continue;
+                }
+
+                let mut vars = Vec::new();
+                sequence_vars(&ts[i], b, &mut vars);
+                let seqs: Vec<&Vec<Binding>> = vars
+                    .iter()
+                    .filter_map(|v| match b.get(v) {
+                        Some(Binding::Many(seq)) => Some(seq),
+                        _ => None,
+                    })
+                    .collect();
+                let len = match seqs.first() {
+                    Some(seq) => seq.len(),
+                    None => {
+                        return Err(LispError::invalid_form(format!(
+                            "No pattern variable before ellipsis in {}",
+                            template
+                        )))
+                    }
+                };
+                if seqs.iter().any(|seq| seq.len() != len) {
+                    return Err(LispError::invalid_form(
+                        "Pattern variables under one ellipsis matched different lengths",
+                    ));
+                }
+
+                for k in 0..len {
+                    let mut inner = b.clone();
+                    for (var, seq) in vars.iter().zip(&seqs) {
+                        inner.insert(var.clone(), seq[k].clone());
+                    }
+                    res.push(instantiate(&ts[i], &inner, rules, renames, rename)?);
+                }
+                i += 2;
+            }
+            Ok(Object::List(res.into(), *span))
+        }
+        o => Ok(o.clone()),
+    }
+}
+
+/// Fresh alias for an identifier introduced by a template. The same
+/// identifier gets the same alias throughout one expansion.
+fn alias(ident: &Object, rules: &SyntaxRules, renames: &mut HashMap<String, Object>) -> Object {
+    let name = ident.to_string();
+    renames
+        .entry(name)
+        .or_insert_with(|| {
+            let id = NEXT_ALIAS.with(|n| {
+                n.set(n.get() + 1);
+                n.get()
+            });
+            Object::Alias(Rc::new(Alias {
+                name: format!("{}#{}", base_name(ident).unwrap_or_default(), id),
+                original: ident.clone(),
+                env: rules.env.clone(),
+            }))
+        })
+        .clone()
+}
@@ -2,26 +2,45 @@ use std::error::Error;
 use std::fs;
 use std::{cell::RefCell, env, rc::Rc};
 
+use crate::error::LispError;
 use crate::scope::Scope;
 use rustyline::error::ReadlineError;
-use rustyline::validate::MatchingBracketValidator;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
 use rustyline::{Cmd, Editor, EventHandler, KeyCode, KeyEvent, Modifiers};
-use rustyline_derive::{Completer, Helper, Highlighter, Hinter, Validator};
+use rustyline_derive::{Completer, Helper, Highlighter, Hinter};
 
+mod builtins;
+mod error;
 mod eval;
 mod lexer;
+mod macros;
+mod number;
 mod object;
 mod parser;
 mod scope;
 
-#[derive(Completer, Helper, Highlighter, Hinter, Validator)]
-struct InputValidator {
-    #[rustyline(Validator)]
-    brackets: MatchingBracketValidator,
+#[derive(Completer, Helper, Highlighter, Hinter)]
+struct InputValidator;
+
+impl Validator for InputValidator {
+    /// Keeps reading lines until every bracket is closed. Brackets are counted
+    /// on lexer tokens so the ones inside strings and characters are ignored.
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        let input = ctx.input();
+        Ok(match lexer::open_brackets(input) {
+            Ok(0) => ValidationResult::Valid(None),
+            Ok(_) => ValidationResult::Incomplete,
+            Err(span) => ValidationResult::Invalid(Some(format!(
+                " - Mismatched brackets: '{}' is unpaired",
+                &input[span.start..span.end]
+            ))),
+        })
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let mut scope = Rc::new(RefCell::new(Scope::new()));
+    builtins::register(&mut scope.borrow_mut());
 
     let args: Vec<String> = env::args().collect();
     if args.len() > 1 {
@@ -36,20 +55,18 @@ fn main() -> Result<(), Box<dyn Error>> {
             println!("\t-h, -?, --help Print this help message and exit");
             return Ok(());
         }
-        
+
         // We have a file path, read it and evaluate it.
         let program_src = fs::read_to_string(args[1].clone())?;
         println!("Program Source:\n{}", program_src);
-        let res_eval = eval::eval(program_src, &mut scope);
+        let res_eval = eval::eval(&program_src, 0, &mut scope);
         match res_eval {
-            Err(s) => println!("Evaluation Error: {}", s),
+            Err(e) => report_error(&program_src, &e),
             Ok(o) => println!("Evaluation Output: {}", o),
         }
     } else {
         // There is not file path in the arguments meaning we are enabling cli mode.
-        let h = InputValidator {
-            brackets: MatchingBracketValidator::new(),
-        };
+        let h = InputValidator;
         let mut rl = Editor::new()?;
         rl.set_helper(Some(h));
         rl.bind_sequence(
@@ -61,14 +78,20 @@ fn main() -> Result<(), Box<dyn Error>> {
             EventHandler::Simple(Cmd::Insert(1, "  ".to_string())),
         );
 
+        // Every line entered so far, so errors inside previously defined lambdas
+        // can still be shown against the line they were written on.
+        let mut session_src = String::new();
         loop {
             let readline = rl.readline("> ");
             match readline {
                 Ok(line) => {
                     rl.add_history_entry(line.as_str());
-                    let res_eval = eval::eval(line, &mut scope);
+                    let start = session_src.len();
+                    session_src.push_str(&line);
+                    session_src.push('\n');
+                    let res_eval = eval::eval(&session_src, start, &mut scope);
                     match res_eval {
-                        Err(s) => println!("Evaluation error: {}", s),
+                        Err(e) => report_error(&session_src, &e),
                         Ok(o) => println!("{}", o),
                     }
                 }
@@ -90,3 +113,39 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+/// Prints an error together with the source line it points at and a caret
+/// underline below the failing form.
+fn report_error(src: &str, err: &LispError) {
+    println!("Evaluation Error: {}", err);
+
+    let span = match err.span {
+        Some(span) if span.start <= span.end && span.end <= src.len() => span,
+        _ => return,
+    };
+
+    let line_start = src[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = src[span.start..]
+        .find('\n')
+        .map_or(src.len(), |i| span.start + i);
+    let line_no = src[..span.start].matches('\n').count() + 1;
+
+    // Keep tabs so the caret lines up with the source line when printed.
+    let padding: String = src[line_start..span.start]
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let width = src[span.start..span.end.min(line_end)]
+        .chars()
+        .count()
+        .max(1);
+
+    let gutter = format!("{} | ", line_no);
+    println!("{}{}", gutter, &src[line_start..line_end]);
+    println!(
+        "{}{}{}",
+        " ".repeat(gutter.len()),
+        padding,
+        "^".repeat(width)
+    );
+}
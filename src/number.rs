@@ -0,0 +1,264 @@
+use std::cmp::Ordering;
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive, Zero};
+
+use crate::error::{ErrorKind, LispError};
+use crate::object::Object;
+
+/// Two numeric operands brought to a common representation, the higher of
+/// the two in the tower integer < bignum < rational < float.
+enum Pair {
+    Int(i64, i64),
+    Big(BigInt, BigInt),
+    Ratio(BigRational, BigRational),
+    Float(f64, f64),
+}
+
+fn rank(obj: &Object) -> Result<u8, LispError> {
+    match obj {
+        Object::Integer(_) => Ok(0),
+        Object::BigInteger(_) => Ok(1),
+        Object::Rational(_) => Ok(2),
+        Object::Float(_) => Ok(3),
+        o => Err(LispError::type_mismatch("Number", o.type_name())),
+    }
+}
+
+fn promote(a: &Object, b: &Object) -> Result<Pair, LispError> {
+    Ok(match (a, b, rank(a)?.max(rank(b)?)) {
+        (Object::Integer(x), Object::Integer(y), _) => Pair::Int(*x, *y),
+        (_, _, 1) => Pair::Big(to_bigint(a), to_bigint(b)),
+        (_, _, 2) => Pair::Ratio(to_ratio(a), to_ratio(b)),
+        _ => Pair::Float(to_float(a)?, to_float(b)?),
+    })
+}
+
+/// Only called on operands ranked at most as bignums.
+fn to_bigint(obj: &Object) -> BigInt {
+    match obj {
+        Object::Integer(n) => BigInt::from(*n),
+        Object::BigInteger(n) => n.clone(),
+        _ => unreachable!("{} is not an integer", obj),
+    }
+}
+
+/// Only called on operands ranked at most as rationals.
+fn to_ratio(obj: &Object) -> BigRational {
+    match obj {
+        Object::Rational(r) => r.clone(),
+        o => BigRational::from_integer(to_bigint(o)),
+    }
+}
+
+/// Demotes to a plain `Integer` whenever the value fits.
+pub fn from_bigint(n: BigInt) -> Object {
+    match n.to_i64() {
+        Some(n) => Object::Integer(n),
+        None => Object::BigInteger(n),
+    }
+}
+
+/// Rationals with a denominator of one become integers.
+pub fn from_ratio(r: BigRational) -> Object {
+    if r.is_integer() {
+        from_bigint(r.to_integer())
+    } else {
+        Object::Rational(r)
+    }
+}
+
+/// Reads an integer, bignum, rational (`1/3`) or float literal.
+pub fn parse(s: &str) -> Option<Object> {
+    if let Ok(n) = s.parse::<i64>() {
+        return Some(Object::Integer(n));
+    }
+    if let Ok(n) = s.parse::<BigInt>() {
+        return Some(from_bigint(n));
+    }
+    if let Some((numer, denom)) = s.split_once('/') {
+        let numer = numer.parse::<BigInt>().ok()?;
+        let denom = denom.parse::<BigInt>().ok()?;
+        if denom.is_zero() {
+            return None;
+        }
+        return Some(from_ratio(BigRational::new(numer, denom)));
+    }
+    s.parse::<f64>().ok().map(Object::Float)
+}
+
+pub fn is_number(obj: &Object) -> bool {
+    rank(obj).is_ok()
+}
+
+pub fn is_float(obj: &Object) -> bool {
+    matches!(obj, Object::Float(_))
+}
+
+pub fn to_float(obj: &Object) -> Result<f64, LispError> {
+    match obj {
+        Object::Integer(n) => Ok(*n as f64),
+        Object::BigInteger(n) => Ok(n.to_f64().unwrap_or(f64::NAN)),
+        Object::Rational(r) => Ok(r.to_f64().unwrap_or(f64::NAN)),
+        Object::Float(f) => Ok(*f),
+        o => Err(LispError::type_mismatch("Number", o.type_name())),
+    }
+}
+
+/// Runs a checked `i64` operation, redoing it on bignums if it overflows.
+fn int_op(
+    x: i64,
+    y: i64,
+    checked: fn(i64, i64) -> Option<i64>,
+    big: fn(BigInt, BigInt) -> BigInt,
+) -> Object {
+    match checked(x, y) {
+        Some(n) => Object::Integer(n),
+        None => from_bigint(big(BigInt::from(x), BigInt::from(y))),
+    }
+}
+
+pub fn add(a: &Object, b: &Object) -> Result<Object, LispError> {
+    Ok(match promote(a, b)? {
+        Pair::Int(x, y) => int_op(x, y, i64::checked_add, |x, y| x + y),
+        Pair::Big(x, y) => from_bigint(x + y),
+        Pair::Ratio(x, y) => from_ratio(x + y),
+        Pair::Float(x, y) => Object::Float(x + y),
+    })
+}
+
+pub fn sub(a: &Object, b: &Object) -> Result<Object, LispError> {
+    Ok(match promote(a, b)? {
+        Pair::Int(x, y) => int_op(x, y, i64::checked_sub, |x, y| x - y),
+        Pair::Big(x, y) => from_bigint(x - y),
+        Pair::Ratio(x, y) => from_ratio(x - y),
+        Pair::Float(x, y) => Object::Float(x - y),
+    })
+}
+
+pub fn mul(a: &Object, b: &Object) -> Result<Object, LispError> {
+    Ok(match promote(a, b)? {
+        Pair::Int(x, y) => int_op(x, y, i64::checked_mul, |x, y| x * y),
+        Pair::Big(x, y) => from_bigint(x * y),
+        Pair::Ratio(x, y) => from_ratio(x * y),
+        Pair::Float(x, y) => Object::Float(x * y),
+    })
+}
+
+/// Exact division yields a rational unless it comes out even; mixing in a
+/// float gives float division.
+pub fn div(a: &Object, b: &Object) -> Result<Object, LispError> {
+    let (x, y) = match promote(a, b)? {
+        Pair::Int(_, 0) => return Err(LispError::new(ErrorKind::DivisionByZero)),
+        Pair::Int(x, y) if x.checked_rem(y) == Some(0) => {
+            return Ok(int_op(x, y, i64::checked_div, |x, y| x / y))
+        }
+        Pair::Int(..) => (to_ratio(a), to_ratio(b)),
+        Pair::Big(x, y) => (BigRational::from_integer(x), BigRational::from_integer(y)),
+        Pair::Ratio(x, y) => (x, y),
+        Pair::Float(x, y) => return Ok(Object::Float(x / y)),
+    };
+    if y.is_zero() {
+        return Err(LispError::new(ErrorKind::DivisionByZero));
+    }
+    Ok(from_ratio(x / y))
+}
+
+/// Brings two integer (or float) operands together for `quotient`, `rem`
+/// and `mod`, which are not defined on rationals.
+fn promote_integers(a: &Object, b: &Object) -> Result<Pair, LispError> {
+    match promote(a, b)? {
+        Pair::Ratio(..) => {
+            let o = if matches!(a, Object::Rational(_)) {
+                a
+            } else {
+                b
+            };
+            Err(LispError::type_mismatch("Integer", o.type_name()))
+        }
+        Pair::Int(_, 0) => Err(LispError::new(ErrorKind::DivisionByZero)),
+        Pair::Big(_, y) if y.is_zero() => Err(LispError::new(ErrorKind::DivisionByZero)),
+        pair => Ok(pair),
+    }
+}
+
+/// Truncating division, `(quotient 7 -2)` is `-3`.
+pub fn quotient(a: &Object, b: &Object) -> Result<Object, LispError> {
+    Ok(match promote_integers(a, b)? {
+        Pair::Int(x, y) => int_op(x, y, i64::checked_div, |x, y| x / y),
+        Pair::Big(x, y) => from_bigint(x / y),
+        Pair::Float(x, y) => Object::Float((x / y).trunc()),
+        Pair::Ratio(..) => unreachable!(),
+    })
+}
+
+/// Remainder with the sign of the dividend, `(rem -7 2)` is `-1`.
+pub fn rem(a: &Object, b: &Object) -> Result<Object, LispError> {
+    Ok(match promote_integers(a, b)? {
+        // Only `i64::MIN % -1` fails, and its remainder is zero.
+        Pair::Int(x, y) => Object::Integer(x.checked_rem(y).unwrap_or(0)),
+        Pair::Big(x, y) => from_bigint(x % y),
+        Pair::Float(x, y) => Object::Float(x % y),
+        Pair::Ratio(..) => unreachable!(),
+    })
+}
+
+/// Modulo with the sign of the divisor, `(mod -7 2)` is `1`.
+pub fn modulo(a: &Object, b: &Object) -> Result<Object, LispError> {
+    Ok(match promote_integers(a, b)? {
+        // The remainder is smaller than `y` in magnitude, so adding `y` to
+        // it cannot overflow.
+        Pair::Int(x, y) => {
+            let r = x.checked_rem(y).unwrap_or(0);
+            Object::Integer(if r != 0 && (r < 0) != (y < 0) {
+                r + y
+            } else {
+                r
+            })
+        }
+        Pair::Big(x, y) => from_bigint(x.mod_floor(&y)),
+        Pair::Float(x, y) => {
+            let r = x % y;
+            Object::Float(if r != 0.0 && (r < 0.0) != (y < 0.0) {
+                r + y
+            } else {
+                r
+            })
+        }
+        Pair::Ratio(..) => unreachable!(),
+    })
+}
+
+pub fn abs(obj: &Object) -> Result<Object, LispError> {
+    match obj {
+        Object::Integer(n) => Ok(match n.checked_abs() {
+            Some(n) => Object::Integer(n),
+            None => from_bigint(BigInt::from(*n).abs()),
+        }),
+        Object::BigInteger(n) => Ok(from_bigint(n.abs())),
+        Object::Rational(r) => Ok(Object::Rational(r.abs())),
+        Object::Float(f) => Ok(Object::Float(f.abs())),
+        o => Err(LispError::type_mismatch("Number", o.type_name())),
+    }
+}
+
+/// Orders two numbers, `None` if either is NaN.
+pub fn compare(a: &Object, b: &Object) -> Result<Option<Ordering>, LispError> {
+    Ok(match promote(a, b)? {
+        Pair::Int(x, y) => Some(x.cmp(&y)),
+        Pair::Big(x, y) => Some(x.cmp(&y)),
+        Pair::Ratio(x, y) => Some(x.cmp(&y)),
+        Pair::Float(x, y) => x.partial_cmp(&y),
+    })
+}
+
+/// Equality for `==`: numbers compare by value across representations,
+/// everything else structurally.
+pub fn equal(a: &Object, b: &Object) -> bool {
+    match compare(a, b) {
+        Ok(ord) => ord == Some(Ordering::Equal),
+        Err(_) => a == b,
+    }
+}
@@ -1,48 +1,698 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::{Deref, Range};
+use std::rc::Rc;
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+use num_bigint::BigInt;
+use num_rational::BigRational;
+
+use crate::error::LispError;
+use crate::scope::Scope;
+
+/// Byte range of a form in the program source.
+///
+/// Spans are only carried around for error reporting, so they never take part
+/// in comparisons: two forms are equal no matter where they were read from.
+/// `Span::default()` marks forms that were built at runtime, such as macro
+/// expansions, and so have no place in the source.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// Whether the span points into the source. Every token is at least one
+    /// byte long, so only `Span::default()` is empty at offset 0.
+    pub fn is_known(&self) -> bool {
+        self.end > 0
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Span {
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
+impl PartialEq for Span {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl PartialOrd for Span {
+    fn partial_cmp(&self, _other: &Self) -> Option<Ordering> {
+        Some(Ordering::Equal)
+    }
+}
+
+#[derive(Debug, Clone, PartialOrd)]
 pub enum Object {
     Void,
     Integer(i64),
+    BigInteger(BigInt),
+    Rational(BigRational),
     Float(f64),
     Bool(bool),
+    Char(char),
+    String(String),
     Operator(Op),
-    Lambda(Vec<String>, Vec<Object>),
+    Lambda(Lambda),
+    Macro(Lambda),
+    Syntax(Rc<SyntaxRules>),
+    Builtin(Builtin),
+    HashMap(HashTable),
+    Vector(Rc<RefCell<Vec<Object>>>),
     Condition,
-    List(Vec<Object>),
+    List(List, Span),
+    Pair(Rc<Pair>),
+    SpecialForm(String),
     Keyword(String),
     Name(String),
+    Alias(Rc<Alias>),
+}
+
+// Lists built by `cons` are equal to lists with the same elements that were
+// read or built by `list`, so pairs are compared element by element. Symbols
+// compare by name, whether they were read as a special form, `if`, an
+// operator or a plain name.
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Object::Void, Object::Void) | (Object::Condition, Object::Condition) => true,
+            (Object::Integer(a), Object::Integer(b)) => a == b,
+            (Object::BigInteger(a), Object::BigInteger(b)) => a == b,
+            (Object::Rational(a), Object::Rational(b)) => a == b,
+            (Object::Float(a), Object::Float(b)) => a == b,
+            (Object::Bool(a), Object::Bool(b)) => a == b,
+            (Object::Char(a), Object::Char(b)) => a == b,
+            (Object::Operator(a), Object::Operator(b)) => a == b,
+            (Object::Lambda(a), Object::Lambda(b)) | (Object::Macro(a), Object::Macro(b)) => a == b,
+            (Object::Syntax(a), Object::Syntax(b)) => a == b,
+            (Object::Builtin(a), Object::Builtin(b)) => a == b,
+            (Object::HashMap(a), Object::HashMap(b)) => a == b,
+            (Object::Vector(a), Object::Vector(b)) => Rc::ptr_eq(a, b) || a == b,
+            (Object::List(a, _), Object::List(b, _)) => a == b,
+            (Object::Pair(_), _) | (_, Object::Pair(_)) => {
+                let (mut a, mut b) = (self.clone(), other.clone());
+                loop {
+                    match (a.uncons(), b.uncons()) {
+                        (Some((a_car, a_cdr)), Some((b_car, b_cdr))) => {
+                            if a_car != b_car {
+                                return false;
+                            }
+                            a = a_cdr;
+                            b = b_cdr;
+                        }
+                        (None, None) => return a == b,
+                        _ => return false,
+                    }
+                }
+            }
+            (Object::String(a), Object::String(b)) | (Object::Keyword(a), Object::Keyword(b)) => {
+                a == b
+            }
+            _ => match (self.symbol_name(), other.symbol_name()) {
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            },
+        }
+    }
+}
+
+/// The elements of a list. Clones share the elements and `tail` leaves out
+/// the first one without copying the rest, so walking a list with `cdr`
+/// takes linear time.
+#[derive(Debug, Clone, Default)]
+pub struct List {
+    items: Rc<[Object]>,
+    start: usize,
+}
+
+impl List {
+    /// Every element but the first, empty for an empty list.
+    pub fn tail(&self) -> List {
+        List {
+            items: self.items.clone(),
+            start: (self.start + 1).min(self.items.len()),
+        }
+    }
+
+    pub fn as_slice(&self) -> &[Object] {
+        &self.items[self.start..]
+    }
+}
+
+impl Deref for List {
+    type Target = [Object];
+
+    fn deref(&self) -> &[Object] {
+        self.as_slice()
+    }
+}
+
+impl From<Vec<Object>> for List {
+    fn from(items: Vec<Object>) -> Self {
+        List {
+            items: items.into(),
+            start: 0,
+        }
+    }
+}
+
+impl FromIterator<Object> for List {
+    fn from_iter<I: IntoIterator<Item = Object>>(iter: I) -> Self {
+        iter.into_iter().collect::<Vec<_>>().into()
+    }
+}
+
+impl<'a> IntoIterator for &'a List {
+    type Item = &'a Object;
+    type IntoIter = std::slice::Iter<'a, Object>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
 
+impl PartialEq for List {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl PartialOrd for List {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.as_slice().partial_cmp(other.as_slice())
+    }
+}
+
+/// A cons cell. `cdr` is usually a list or another pair, which makes a list
+/// that shares its tail, but may be any value.
+#[derive(Debug, PartialEq, PartialOrd)]
+pub struct Pair {
+    pub car: Object,
+    pub cdr: Object,
+}
+
+// Dropping a long chain of pairs recursively would overflow the stack, so
+// the tail is unlinked one pair at a time.
+impl Drop for Pair {
+    fn drop(&mut self) {
+        let mut next = mem::replace(&mut self.cdr, Object::Void);
+        while let Object::Pair(pair) = next {
+            match Rc::try_unwrap(pair) {
+                Ok(mut pair) => next = mem::replace(&mut pair.cdr, Object::Void),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+/// Characters written by name, as in `#\space`.
+pub const CHAR_NAMES: &[(&str, char)] = &[
+    ("space", ' '),
+    ("newline", '\n'),
+    ("tab", '\t'),
+    ("return", '\r'),
+    ("nul", '\0'),
+];
+
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Object::Void => write!(f, "Void"),
             Object::Integer(n) => write!(f, "{}", n),
-            Object::Float(n) => write!(f, "{}", n),
+            Object::BigInteger(n) => write!(f, "{}", n),
+            Object::Rational(r) => write!(f, "{}", r),
+            Object::Float(n) => write!(f, "{:?}", n),
             Object::Bool(b) => write!(f, "{}", b),
+            Object::Char(c) => match CHAR_NAMES.iter().find(|(_, named)| named == c) {
+                Some((name, _)) => write!(f, "#\\{}", name),
+                None => write!(f, "#\\{}", c),
+            },
+            Object::String(s) => write!(f, "{:?}", s),
             Object::Operator(o) => write!(f, "{}", o),
-            Object::Lambda(vs, vo) => {
-                write!(f, "lambda [")?;
-                for s in vs {
-                    write!(f, " {}", s)?;
-                }
-                write!(f, " ] (")?;
-                for o in vo {
+            Object::Lambda(l) => {
+                write!(f, "lambda [{} ] (", l.params)?;
+                for o in l.body.iter() {
                     write!(f, " {}", o)?;
                 }
                 write!(f, " )")
             }
-            Object::Condition => write!(f, "If"),
-            Object::List(l) => {
-                write!(f, "(")?;
-                for o in l {
+            Object::Macro(l) => {
+                write!(f, "macro [{} ] (", l.params)?;
+                for o in l.body.iter() {
                     write!(f, " {}", o)?;
                 }
                 write!(f, " )")
             }
-            Object::Keyword(s) => write!(f, "{}", s),
+            Object::Syntax(_) => write!(f, "syntax-rules"),
+            Object::Builtin(b) => write!(f, "builtin {}", b.name),
+            Object::HashMap(table) => {
+                write!(f, "{{")?;
+                for (i, (k, v)) in table.0.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{} {}", k.0, v)?;
+                }
+                write!(f, "}}")
+            }
+            Object::Vector(v) => {
+                write!(f, "#(")?;
+                for (i, o) in v.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", o)?;
+                }
+                write!(f, ")")
+            }
+            Object::Condition => write!(f, "if"),
+            Object::List(l, _) => {
+                // Vector and hash map literals that have not been evaluated
+                // yet.
+                let (open, l, close) = match l.split_first() {
+                    Some((Object::SpecialForm(k), rest)) if k == "vector" => ("#(", rest, ")"),
+                    Some((Object::SpecialForm(k), rest)) if k == "hash-map" => ("{", rest, "}"),
+                    _ => ("(", l.as_slice(), ")"),
+                };
+                write!(f, "{}", open)?;
+                for (i, o) in l.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", o)?;
+                }
+                write!(f, "{}", close)
+            }
+            Object::Pair(pair) => {
+                write!(f, "({}", pair.car)?;
+                let mut rest = pair.cdr.clone();
+                loop {
+                    match rest {
+                        Object::Pair(pair) => {
+                            write!(f, " {}", pair.car)?;
+                            rest = pair.cdr.clone();
+                        }
+                        Object::List(l, _) => {
+                            for o in l.iter() {
+                                write!(f, " {}", o)?;
+                            }
+                            break;
+                        }
+                        o => {
+                            write!(f, " . {}", o)?;
+                            break;
+                        }
+                    }
+                }
+                write!(f, ")")
+            }
+            Object::SpecialForm(s) => write!(f, "{}", s),
+            Object::Keyword(s) => write!(f, ":{}", s),
             Object::Name(s) => write!(f, "{}", s),
+            Object::Alias(alias) => write!(f, "{}", alias.name),
+        }
+    }
+}
+
+/// Parameter list of a lambda: `(a b &optional (c 1) d &rest more)`.
+/// Optional parameters without a default form are bound to `Void`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Params {
+    pub required: Vec<String>,
+    pub optional: Vec<(String, Option<Object>)>,
+    pub rest: Option<String>,
+}
+
+impl Params {
+    /// Smallest and, unless there is a rest parameter, largest number of
+    /// arguments the lambda accepts.
+    pub fn arity(&self) -> (usize, Option<usize>) {
+        let min = self.required.len();
+        match self.rest {
+            Some(_) => (min, None),
+            None => (min, Some(min + self.optional.len())),
+        }
+    }
+}
+
+impl fmt::Display for Params {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for s in &self.required {
+            write!(f, " {}", s)?;
+        }
+        if !self.optional.is_empty() {
+            write!(f, " &optional")?;
+            for (s, default) in &self.optional {
+                match default {
+                    Some(o) => write!(f, " ({} {})", s, o)?,
+                    None => write!(f, " {}", s)?,
+                }
+            }
+        }
+        if let Some(s) = &self.rest {
+            write!(f, " &rest {}", s)?;
+        }
+        Ok(())
+    }
+}
+
+/// A function value together with the scope it was created in. Calls extend
+/// `env` rather than the caller's scope, which gives lambdas lexical scoping.
+#[derive(Clone)]
+pub struct Lambda {
+    pub params: Params,
+    pub body: Rc<[Object]>,
+    pub env: Rc<RefCell<Scope>>,
+}
+
+// The captured scope usually contains the lambda itself, so it is left out of
+// `Debug` and compared by identity to avoid recursing forever.
+impl fmt::Debug for Lambda {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Lambda")
+            .field("params", &self.params)
+            .field("body", &self.body)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Lambda {
+    fn eq(&self, other: &Self) -> bool {
+        self.params == other.params && self.body == other.body && Rc::ptr_eq(&self.env, &other.env)
+    }
+}
+
+impl PartialOrd for Lambda {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            Some(Ordering::Equal)
+        } else {
+            None
+        }
+    }
+}
+
+/// A `syntax-rules` transformer: `(pattern template)` pairs tried in order.
+/// Free identifiers introduced by a template are looked up in `env`.
+pub struct SyntaxRules {
+    pub literals: Vec<String>,
+    pub rules: Vec<(Object, Object)>,
+    pub env: Rc<RefCell<Scope>>,
+}
+
+impl fmt::Debug for SyntaxRules {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SyntaxRules")
+            .field("literals", &self.literals)
+            .field("rules", &self.rules)
+            .finish_non_exhaustive()
+    }
+}
+
+// Transformers are only ever shared through an `Rc`, so identity is enough.
+impl PartialEq for SyntaxRules {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl PartialOrd for SyntaxRules {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            Some(Ordering::Equal)
+        } else {
+            None
+        }
+    }
+}
+
+/// An identifier introduced by a `syntax-rules` template. It is bound under
+/// `name`, which is unique to one expansion, and otherwise refers to
+/// `original` as seen from `env`, the scope the macro was defined in.
+pub struct Alias {
+    pub name: String,
+    pub original: Object,
+    pub env: Rc<RefCell<Scope>>,
+}
+
+impl fmt::Debug for Alias {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Alias")
+            .field("name", &self.name)
+            .field("original", &self.original)
+            .finish_non_exhaustive()
+    }
+}
+
+// Every expansion gets fresh names, so the name identifies an alias.
+impl PartialEq for Alias {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl PartialOrd for Alias {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.name.partial_cmp(&other.name)
+    }
+}
+
+/// A value usable as a hash map key. Only types with a well-behaved equality
+/// qualify, which leaves out floats and procedures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashKey(pub Object);
+
+impl HashKey {
+    pub fn new(obj: &Object) -> Result<Self, LispError> {
+        match obj {
+            Object::Integer(_)
+            | Object::BigInteger(_)
+            | Object::Rational(_)
+            | Object::Bool(_)
+            | Object::Char(_)
+            | Object::String(_)
+            | Object::Keyword(_)
+            | Object::Name(_) => Ok(HashKey(obj.clone())),
+            // Other symbols are stored as plain names so they hash the same.
+            o => match o.symbol_name() {
+                Some(name) => Ok(HashKey(Object::Name(name.into_owned()))),
+                None => Err(LispError::type_mismatch("hashable value", o.type_name())),
+            },
+        }
+    }
+}
+
+impl Eq for HashKey {}
+
+impl Hash for HashKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(&self.0).hash(state);
+        match &self.0 {
+            Object::Integer(n) => n.hash(state),
+            Object::BigInteger(n) => n.hash(state),
+            Object::Rational(r) => r.hash(state),
+            Object::Bool(b) => b.hash(state),
+            Object::Char(c) => c.hash(state),
+            Object::String(s) | Object::Keyword(s) | Object::Name(s) => s.hash(state),
+            _ => unreachable!("{} is not hashable", self.0),
+        }
+    }
+}
+
+/// A mutable hash map. Clones share the same table, and equality compares
+/// the contents.
+#[derive(Debug, Clone, Default)]
+pub struct HashTable(pub Rc<RefCell<HashMap<HashKey, Object>>>);
+
+impl HashTable {
+    /// Builds a table from alternating keys and values; a trailing key
+    /// without a value is ignored.
+    pub fn from_pairs(elements: &[Object]) -> Result<Self, LispError> {
+        let table = HashTable::default();
+        for pair in elements.chunks_exact(2) {
+            table
+                .0
+                .borrow_mut()
+                .insert(HashKey::new(&pair[0])?, pair[1].clone());
+        }
+        Ok(table)
+    }
+}
+
+impl PartialEq for HashTable {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0) || *self.0.borrow() == *other.0.borrow()
+    }
+}
+
+impl PartialOrd for HashTable {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            Some(Ordering::Equal)
+        } else {
+            None
+        }
+    }
+}
+
+pub type BuiltinFn = fn(&[Object]) -> Result<Object, LispError>;
+
+/// A procedure implemented in Rust. Builtins are identified by their name.
+#[derive(Clone)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub func: BuiltinFn,
+}
+
+impl fmt::Debug for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Builtin({})", self.name)
+    }
+}
+
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl PartialOrd for Builtin {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.name.partial_cmp(other.name)
+    }
+}
+
+impl Object {
+    /// The empty list, also written `nil` or `'()`.
+    pub fn nil() -> Object {
+        Object::List(List::default(), Span::default())
+    }
+
+    /// The name of a symbol. Quoted special form names, `if` and operators
+    /// are symbols as well as plain names.
+    pub fn symbol_name(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Object::Name(s) | Object::SpecialForm(s) => Some(Cow::Borrowed(s)),
+            Object::Alias(alias) => Some(Cow::Borrowed(&alias.name)),
+            Object::Condition => Some(Cow::Borrowed("if")),
+            Object::Operator(op) => Some(Cow::Owned(op.to_string())),
+            _ => None,
+        }
+    }
+
+    /// The name an identifier is bound under.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Object::Name(s) => Some(s),
+            Object::Alias(alias) => Some(&alias.name),
+            _ => None,
+        }
+    }
+
+    /// Builds `(items ... . tail)`, which is a plain list when `tail` is a
+    /// proper list and a chain of pairs otherwise.
+    pub fn dotted(mut items: Vec<Object>, tail: Object, span: Span) -> Object {
+        if let Some(rest) = tail.list_items() {
+            items.extend_from_slice(&rest);
+            return Object::List(items.into(), span);
+        }
+        items
+            .into_iter()
+            .rev()
+            .fold(tail, |cdr, car| Object::Pair(Rc::new(Pair { car, cdr })))
+    }
+
+    /// Splits a chain of pairs into its elements and the final `cdr`, which
+    /// is the value itself for anything but a pair.
+    pub fn split_tail(&self) -> (Vec<Object>, Object) {
+        let mut items = Vec::new();
+        let mut rest = self.clone();
+        while let Object::Pair(pair) = rest {
+            items.push(pair.car.clone());
+            rest = pair.cdr.clone();
+        }
+        (items, rest)
+    }
+
+    /// Splits a pair or non-empty list into its first element and the rest.
+    pub fn uncons(&self) -> Option<(Object, Object)> {
+        match self {
+            Object::Pair(pair) => Some((pair.car.clone(), pair.cdr.clone())),
+            Object::List(l, _) => l
+                .first()
+                .map(|car| (car.clone(), Object::List(l.tail(), Span::default()))),
+            _ => None,
+        }
+    }
+
+    /// The elements of a proper list, whether it was built from pairs or
+    /// not. `None` for any other value, improper lists included.
+    pub fn list_items(&self) -> Option<Cow<'_, [Object]>> {
+        let mut items = Vec::new();
+        let mut rest = match self {
+            Object::List(l, _) => return Some(Cow::Borrowed(l)),
+            Object::Pair(pair) => pair.clone(),
+            _ => return None,
+        };
+        loop {
+            items.push(rest.car.clone());
+            rest = match &rest.cdr {
+                Object::Pair(pair) => pair.clone(),
+                Object::List(l, _) => {
+                    items.extend_from_slice(l);
+                    return Some(Cow::Owned(items));
+                }
+                _ => return None,
+            };
+        }
+    }
+
+    /// `#f`, the empty list and `Void` are false in conditions; every other
+    /// value, including `0`, `""` and empty vectors or hash maps, is true.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Object::Bool(b) => *b,
+            Object::List(l, _) => !l.is_empty(),
+            Object::Void => false,
+            _ => true,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Void => "Void",
+            Object::Integer(_) | Object::BigInteger(_) => "Integer",
+            Object::Rational(_) => "Rational",
+            Object::Float(_) => "Float",
+            Object::Bool(_) => "Bool",
+            Object::Char(_) => "Char",
+            Object::String(_) => "String",
+            Object::Operator(_) => "Operator",
+            Object::Lambda(_) => "Lambda",
+            Object::Macro(_) => "Macro",
+            Object::Syntax(_) => "Syntax",
+            Object::Builtin(_) => "Builtin",
+            Object::HashMap(_) => "HashMap",
+            Object::Vector(_) => "Vector",
+            Object::Condition => "If",
+            Object::List(_, _) => "List",
+            Object::Pair(_) => "Pair",
+            Object::SpecialForm(_) => "SpecialForm",
+            Object::Keyword(_) => "Keyword",
+            Object::Name(_) | Object::Alias(_) => "Name",
         }
     }
 }
@@ -56,7 +706,9 @@ pub enum Op {
     Eq,
     NotEq,
     Greater,
+    GreaterEq,
     Smaller,
+    SmallerEq,
 }
 
 impl fmt::Display for Op {
@@ -69,7 +721,9 @@ impl fmt::Display for Op {
             Op::Eq => write!(f, "=="),
             Op::NotEq => write!(f, "!="),
             Op::Greater => write!(f, ">"),
+            Op::GreaterEq => write!(f, ">="),
             Op::Smaller => write!(f, "<"),
+            Op::SmallerEq => write!(f, "<="),
         }
     }
 }
@@ -1,73 +1,253 @@
+use crate::error::LispError;
+use crate::number;
 use crate::object::*;
-use std::{
-    error::Error,
-    fmt::{self, Display, Formatter},
-};
 
 use crate::lexer::*;
 
-#[derive(Debug)]
-pub struct ParserError {
-    err: String,
+/// Reads every top-level form from the (reversed) token stream, together
+/// with the span it was read from.
+pub fn parse(lexer_tokens: &mut Vec<(LexerToken, Span)>) -> Result<Vec<(Object, Span)>, LispError> {
+    let mut forms = Vec::new();
+    while !lexer_tokens.is_empty() {
+        forms.push(parse_datum(lexer_tokens)?);
+    }
+    Ok(forms)
 }
 
-impl Display for ParserError {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "Parse error: {}", self.err)
+/// Reads a single atom or list and returns it with its span.
+fn parse_datum(lexer_tokens: &mut Vec<(LexerToken, Span)>) -> Result<(Object, Span), LispError> {
+    match lexer_tokens.pop() {
+        Some((LexerToken::ParenthesesOpen, span)) => {
+            let list = parse_list(lexer_tokens, span)?;
+            let span = match &list {
+                Object::List(_, span) => *span,
+                _ => span,
+            };
+            Ok((list, span))
+        }
+        Some((LexerToken::ParenthesesClose, span)) => {
+            Err(LispError::parse("Unexpected Parentheses Close ')'", span))
+        }
+        Some((LexerToken::VectorOpen, span)) => parse_vector(lexer_tokens, span),
+        Some((LexerToken::BraceOpen, span)) => parse_hash_map(lexer_tokens, span),
+        Some((LexerToken::BraceClose, span)) => {
+            Err(LispError::parse("Unexpected Brace Close '}'", span))
+        }
+        Some((LexerToken::Quote, span)) => parse_reader_macro("quote", lexer_tokens, span),
+        Some((LexerToken::Quasiquote, span)) => {
+            parse_reader_macro("quasiquote", lexer_tokens, span)
+        }
+        Some((LexerToken::Unquote, span)) => parse_reader_macro("unquote", lexer_tokens, span),
+        Some((LexerToken::UnquoteSplicing, span)) => {
+            parse_reader_macro("unquote-splicing", lexer_tokens, span)
+        }
+        Some((token, span)) => Ok((parse_atom(token, span)?, span)),
+        None => Err(LispError::parse("Unexpected end of input", Span::default())),
     }
 }
 
-impl Error for ParserError {}
+/// Expands `'x`, `` `x ``, `,x` and `,@x` into `(keyword x)`.
+fn parse_reader_macro(
+    keyword: &str,
+    lexer_tokens: &mut Vec<(LexerToken, Span)>,
+    start: Span,
+) -> Result<(Object, Span), LispError> {
+    if lexer_tokens.is_empty() {
+        return Err(LispError::parse(
+            format!("Expected a form after {}", keyword),
+            start,
+        ));
+    }
+    let (datum, datum_span) = parse_datum(lexer_tokens)?;
+    let span = Span {
+        start: start.start,
+        end: datum_span.end,
+    };
+    Ok((
+        Object::List(
+            vec![Object::SpecialForm(keyword.to_string()), datum].into(),
+            span,
+        ),
+        span,
+    ))
+}
+
+fn parse_atom(token: LexerToken, span: Span) -> Result<Object, LispError> {
+    let obj = match token {
+        LexerToken::Integer(n) => Object::Integer(n),
+        LexerToken::BigInteger(s) | LexerToken::Rational(s) => number::parse(s)
+            .ok_or_else(|| LispError::parse(format!("Invalid number literal {}", s), span))?,
+        LexerToken::Float(n) => Object::Float(n),
+        LexerToken::Bool(b) => Object::Bool(b),
+        LexerToken::Char(s) => Object::Char(parse_char(s, span)?),
+        LexerToken::String(s) => Object::String(unescape(s, span)?),
+        LexerToken::OpAdd => Object::Operator(Op::Add),
+        LexerToken::OpSub => Object::Operator(Op::Sub),
+        LexerToken::OpMul => Object::Operator(Op::Mul),
+        LexerToken::OpDiv => Object::Operator(Op::Div),
+        LexerToken::OpEqual => Object::Operator(Op::Eq),
+        LexerToken::OpNotEqual => Object::Operator(Op::NotEq),
+        LexerToken::OpGreater => Object::Operator(Op::Greater),
+        LexerToken::OpGreaterEq => Object::Operator(Op::GreaterEq),
+        LexerToken::OpSmaller => Object::Operator(Op::Smaller),
+        LexerToken::OpSmallerEq => Object::Operator(Op::SmallerEq),
+        LexerToken::SpecialForm(s) => Object::SpecialForm(s.to_string()),
+        LexerToken::Keyword(s) => Object::Keyword(s.to_string()),
+        LexerToken::Name(s) => Object::Name(s.to_string()),
+        LexerToken::If => Object::Condition,
+        LexerToken::Nil => Object::List(List::default(), span),
+        LexerToken::ParenthesesOpen
+        | LexerToken::ParenthesesClose
+        | LexerToken::VectorOpen
+        | LexerToken::BraceOpen
+        | LexerToken::BraceClose
+        | LexerToken::Quote
+        | LexerToken::Quasiquote
+        | LexerToken::Unquote
+        | LexerToken::UnquoteSplicing
+        | LexerToken::Dot
+        | LexerToken::Error => return Err(LispError::parse("Unexpected character", span)),
+    };
+    Ok(obj)
+}
+
+/// Reads the elements of a list whose opening parenthesis at `start` has
+/// already been consumed. A list with a dotted tail, as in `(a b . c)`, is
+/// made of pairs unless the tail is itself a list.
+fn parse_list(
+    lexer_tokens: &mut Vec<(LexerToken, Span)>,
+    start: Span,
+) -> Result<Object, LispError> {
+    let (list, tail, span) = parse_elements(
+        lexer_tokens,
+        start,
+        LexerToken::ParenthesesClose,
+        "Unclosed Parentheses '('",
+    )?;
+    Ok(match tail {
+        Some(tail) => Object::dotted(list, tail, span),
+        None => Object::List(list.into(), span),
+    })
+}
 
-pub fn parse(lexer_tokens: &mut Vec<LexerToken>) -> Result<Object, ParserError> {
-    parse_list(lexer_tokens)
+/// Reads `#(a b ...)` as the special form `(vector a b ...)`, which user
+/// bindings of `vector` cannot shadow.
+fn parse_vector(
+    lexer_tokens: &mut Vec<(LexerToken, Span)>,
+    start: Span,
+) -> Result<(Object, Span), LispError> {
+    let (mut elements, _, span) = parse_elements(
+        lexer_tokens,
+        start,
+        LexerToken::ParenthesesClose,
+        "Unclosed Vector '#('",
+    )?;
+    elements.insert(0, Object::SpecialForm("vector".to_string()));
+    Ok((Object::List(elements.into(), span), span))
 }
 
-fn parse_list(lexer_tokens: &mut Vec<LexerToken>) -> Result<Object, ParserError> {
-    let token = lexer_tokens.pop();
-    if token != Some(LexerToken::ParenthesesOpen) {
-        return Err(ParserError {
-            err: format!("Expected Parentheses Open '(', but found {:?}\n", token),
-        });
+/// Reads `{k1 v1 k2 v2 ...}` as the special form `(hash-map k1 v1 k2 v2 ...)`,
+/// which evaluates keys and values like the arguments of a call.
+fn parse_hash_map(
+    lexer_tokens: &mut Vec<(LexerToken, Span)>,
+    start: Span,
+) -> Result<(Object, Span), LispError> {
+    let (mut elements, _, span) = parse_elements(
+        lexer_tokens,
+        start,
+        LexerToken::BraceClose,
+        "Unclosed Brace '{'",
+    )?;
+    // A splice may supply any number of elements, so quasiquote checks those.
+    let spliced = elements.iter().any(|o| {
+        matches!(o, Object::List(l, _)
+            if matches!(l.first(), Some(Object::SpecialForm(k)) if k == "unquote-splicing"))
+    });
+    if !spliced && !elements.len().is_multiple_of(2) {
+        return Err(LispError::parse(
+            "Hash map literal needs a value for every key",
+            span,
+        ));
     }
+    elements.insert(0, Object::SpecialForm("hash-map".to_string()));
+    Ok((Object::List(elements.into(), span), span))
+}
 
-    let mut list: Vec<Object> = Vec::new();
-    while !lexer_tokens.is_empty() {
-        let token = lexer_tokens.pop();
-        if token == None {
-            return Err(ParserError {
-                err: "Insufficient tokens\n".to_string(),
-            });
+/// Reads data up to the `close` token, failing with `unclosed` if the input
+/// runs out first. Only lists may have a dotted tail, which is returned
+/// separately from the elements before the dot.
+fn parse_elements(
+    lexer_tokens: &mut Vec<(LexerToken, Span)>,
+    start: Span,
+    close: LexerToken,
+    unclosed: &str,
+) -> Result<(Vec<Object>, Option<Object>, Span), LispError> {
+    let mut elements: Vec<Object> = Vec::new();
+    let mut tail = None;
+    while let Some((token, span)) = lexer_tokens.last() {
+        if *token == close {
+            let end = span.end;
+            lexer_tokens.pop();
+            return Ok((
+                elements,
+                tail,
+                Span {
+                    start: start.start,
+                    end,
+                },
+            ));
+        }
+        let dotted = *token == LexerToken::Dot && close == LexerToken::ParenthesesClose;
+        if tail.is_some() || (dotted && elements.is_empty()) {
+            return Err(LispError::parse("Misplaced '.' in list", *span));
         }
+        if dotted {
+            lexer_tokens.pop();
+            tail = Some(parse_datum(lexer_tokens)?.0);
+            continue;
+        }
+        elements.push(parse_datum(lexer_tokens)?.0);
+    }
+
+    Err(LispError::parse(unclosed, start))
+}
 
-        let t = token.unwrap();
+/// Resolves the text after `#\`, either a single character or a name.
+fn parse_char(s: &str, span: Span) -> Result<char, LispError> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(c);
+    }
+    CHAR_NAMES
+        .iter()
+        .find(|(name, _)| *name == s)
+        .map(|(_, c)| *c)
+        .ok_or_else(|| LispError::parse(format!("Unknown character name '{}'", s), span))
+}
 
-        match t {
-            LexerToken::Integer(n) => list.push(Object::Integer(n)),
-            LexerToken::Float(n) => list.push(Object::Float(n)),
-            LexerToken::Bool(b) => list.push(Object::Bool(b)),
-            LexerToken::OpAdd => list.push(Object::Operator(Op::Add)),
-            LexerToken::OpSub => list.push(Object::Operator(Op::Sub)),
-            LexerToken::OpMul => list.push(Object::Operator(Op::Mul)),
-            LexerToken::OpDiv => list.push(Object::Operator(Op::Div)),
-            LexerToken::OpEqual => list.push(Object::Operator(Op::Eq)),
-            LexerToken::OpNotEqual => list.push(Object::Operator(Op::NotEq)),
-            LexerToken::OpGreater => list.push(Object::Operator(Op::Greater)),
-            LexerToken::OpSmaller => list.push(Object::Operator(Op::Smaller)),
-            LexerToken::ParenthesesOpen => {
-                lexer_tokens.push(LexerToken::ParenthesesOpen);
-                let sub_list = parse_list(lexer_tokens)?;
-                list.push(sub_list);
-            }
-            LexerToken::ParenthesesClose => {
-                return Ok(Object::List(list));
+fn unescape(s: &str, span: Span) -> Result<String, LispError> {
+    let mut res = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => res.push('\n'),
+            Some('t') => res.push('\t'),
+            Some('r') => res.push('\r'),
+            Some('0') => res.push('\0'),
+            Some('\\') => res.push('\\'),
+            Some('"') => res.push('"'),
+            Some(c) => {
+                return Err(LispError::parse(
+                    format!("Unknown escape sequence '\\{}'", c),
+                    span,
+                ))
             }
-            LexerToken::Keyword(s) => list.push(Object::Keyword(s.to_string())),
-            LexerToken::Name(s) => list.push(Object::Name(s.to_string())),
-            LexerToken::If => list.push(Object::Condition),
-            LexerToken::Error => (),
+            None => return Err(LispError::parse("Unterminated escape sequence", span)),
         }
     }
-
-    Ok(Object::List(list))
+    Ok(res)
 }
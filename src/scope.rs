@@ -35,4 +35,19 @@ impl Scope {
     pub fn set(&mut self, name: &str, val: Object) {
         self.vars.insert(name.to_string(), val);
     }
+
+    /// Updates an existing binding in the innermost scope that has it. The value
+    /// is handed back if the name is not bound anywhere.
+    pub fn assign(&mut self, name: &str, val: Object) -> Result<(), Object> {
+        match self.vars.get_mut(name) {
+            Some(v) => {
+                *v = val;
+                Ok(())
+            }
+            None => match &self.parent {
+                Some(parent) => parent.borrow_mut().assign(name, val),
+                None => Err(val),
+            },
+        }
+    }
 }